@@ -0,0 +1,424 @@
+//! Send PagerDuty events from shell scripts and cron jobs.
+//!
+//! Prints the incident key (or v2 dedup key) of the event on success. Exits
+//! with 1 if the event couldn't be sent and 2 on usage errors. `run` exits
+//! with the status of the command instead.
+
+extern crate pagersduty;
+extern crate serde_json;
+extern crate sha2;
+
+use std::env;
+use std::fs::File;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+
+use serde_json::Value;
+
+use pagersduty::events::Details;
+use pagersduty::events::v1::{self, ErrorResponse};
+use pagersduty::events::v2;
+
+mod run;
+
+static USAGE: &'static str = "\
+Usage: pd-event <trigger|ack|resolve> [options]
+       pd-event run [options] -- COMMAND [ARGS...]
+
+Run executes COMMAND, triggers an incident with the tail of its output when it
+fails or times out, and resolves the incident once it succeeds again.
+
+Options:
+    --key KEY               incident key (v1) or dedup key (v2); required for ack and resolve
+    --description TEXT      description (v1) or summary (v2); required for trigger
+    --detail NAME=VALUE     add a detail to a trigger event; may be repeated
+    --link URL              attach a link to a trigger event; may be repeated
+    --client NAME           name of the monitoring client
+    --client-url URL        URL of the monitoring client
+    --v2                    use the Events API v2
+    --source SOURCE         affected system for v2 trigger events [default: pd-event]
+    --severity SEVERITY     critical, error, warning or info for v2 [default: error]
+    --timeout SECS          kill the command and trigger after SECS seconds; the command
+                            then runs outside job control, with no stdin (run)
+    --tail LINES            lines of output to include in the incident [default: 20] (run)
+    --state-dir DIR         where to remember open incidents, one file per key [default: ~/.pagerduty-run] (run)
+    --routing-key KEY       service or routing key
+    --config PATH           JSON config file with a \"routing_key\"
+    -h, --help              show this message
+
+The routing key is taken from --routing-key, then $PAGERDUTY_ROUTING_KEY, then the
+config file: --config, $PAGERDUTY_CONFIG or ~/.pagerduty.json.";
+
+const ROUTING_KEY_VAR: &'static str = "PAGERDUTY_ROUTING_KEY";
+
+const CONFIG_VAR: &'static str = "PAGERDUTY_CONFIG";
+
+const DEFAULT_CONFIG: &'static str = ".pagerduty.json";
+
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Command {
+    Trigger,
+    Acknowledge,
+    Resolve,
+    Run,
+}
+
+#[derive(Debug, PartialEq)]
+struct Args {
+    command: Command,
+    key: Option<String>,
+    description: Option<String>,
+    details: Vec<(String, String)>,
+    links: Vec<String>,
+    client: Option<String>,
+    client_url: Option<String>,
+    v2: bool,
+    source: String,
+    severity: v2::Severity,
+    routing_key: Option<String>,
+    config: Option<PathBuf>,
+    timeout: Option<Duration>,
+    tail: usize,
+    state_dir: Option<PathBuf>,
+    command_line: Vec<String>,
+}
+
+/// Why the arguments couldn't be used.
+#[derive(Debug, PartialEq)]
+enum Usage {
+    Help,
+    Error(String),
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Args, Usage> {
+    let command = match args.next().as_ref().map(|command| command.as_str()) {
+        Some("trigger") => Command::Trigger,
+        Some("ack") | Some("acknowledge") => Command::Acknowledge,
+        Some("resolve") => Command::Resolve,
+        Some("run") => Command::Run,
+        Some("-h") | Some("--help") => return Err(Usage::Help),
+        Some(command) => return Err(Usage::Error(format!("unknown command {}", command))),
+        None => return Err(Usage::Error("missing command".into())),
+    };
+
+    let mut parsed = Args {
+        command: command,
+        key: None,
+        description: None,
+        details: vec![],
+        links: vec![],
+        client: None,
+        client_url: None,
+        v2: false,
+        source: "pd-event".into(),
+        severity: v2::Severity::Error,
+        routing_key: None,
+        config: None,
+        timeout: None,
+        tail: 20,
+        state_dir: None,
+        command_line: vec![],
+    };
+
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            return Err(Usage::Help);
+        }
+        if arg == "--v2" {
+            parsed.v2 = true;
+            continue;
+        }
+        if arg == "--" {
+            parsed.command_line = args.collect();
+            break;
+        }
+
+        let value = match args.next() {
+            Some(value) => value,
+            None => return Err(Usage::Error(format!("{} needs a value", arg))),
+        };
+        match arg.as_str() {
+            "--key" => parsed.key = Some(value),
+            "--description" => parsed.description = Some(value),
+            "--detail" => {
+                let mut parts = value.splitn(2, '=');
+                match (parts.next(), parts.next()) {
+                    (Some(name), Some(value)) if !name.is_empty() => {
+                        parsed.details.push((name.into(), value.into()));
+                    },
+                    _ => return Err(Usage::Error(format!("--detail {} isn't NAME=VALUE", value))),
+                }
+            },
+            "--link" => parsed.links.push(value),
+            "--client" => parsed.client = Some(value),
+            "--client-url" => parsed.client_url = Some(value),
+            "--source" => parsed.source = value,
+            "--severity" => {
+                parsed.severity = serde_json::from_value(Value::String(value.clone()))
+                    .map_err(|_| Usage::Error(format!("unknown severity {}", value)))?;
+            },
+            "--routing-key" => parsed.routing_key = Some(value),
+            "--config" => parsed.config = Some(value.into()),
+            "--timeout" => {
+                let secs = value.parse()
+                    .map_err(|_| Usage::Error(format!("--timeout {} isn't a number of seconds", value)))?;
+                parsed.timeout = Some(Duration::from_secs(secs));
+            },
+            "--tail" => {
+                parsed.tail = value.parse()
+                    .map_err(|_| Usage::Error(format!("--tail {} isn't a number of lines", value)))?;
+            },
+            "--state-dir" => parsed.state_dir = Some(value.into()),
+            _ => return Err(Usage::Error(format!("unknown option {}", arg))),
+        }
+    }
+
+    match parsed.command {
+        Command::Run if parsed.command_line.is_empty() => {
+            Err(Usage::Error("run needs a command after --".into()))
+        },
+        Command::Run if parsed.v2 => Err(Usage::Error("run only sends v1 events".into())),
+        Command::Trigger | Command::Acknowledge | Command::Resolve if !parsed.command_line.is_empty() => {
+            Err(Usage::Error("only run takes a command".into()))
+        },
+        Command::Trigger if parsed.description.is_none() => {
+            Err(Usage::Error("trigger needs --description".into()))
+        },
+        Command::Acknowledge | Command::Resolve if parsed.key.is_none() => {
+            Err(Usage::Error("ack and resolve need --key".into()))
+        },
+        _ => Ok(parsed),
+    }
+}
+
+/// The routing key from the arguments, environment or config file.
+fn routing_key(args: &Args) -> Result<String, String> {
+    if let Some(ref routing_key) = args.routing_key {
+        return Ok(routing_key.clone());
+    }
+    if let Ok(routing_key) = env::var(ROUTING_KEY_VAR) {
+        return Ok(routing_key);
+    }
+
+    // Only a config file that was asked for has to exist.
+    let (path, required) = match args.config {
+        Some(ref path) => (path.clone(), true),
+        None => match env::var_os(CONFIG_VAR) {
+            Some(path) => (path.into(), true),
+            None => match env::var_os("HOME") {
+                Some(home) => (PathBuf::from(home).join(DEFAULT_CONFIG), false),
+                None => return Err("no routing key given".into()),
+            },
+        },
+    };
+
+    let config: Value = match File::open(&path) {
+        Ok(file) => serde_json::from_reader(file)
+            .map_err(|err| format!("can't read {}: {}", path.display(), err))?,
+        Err(ref err) if required => return Err(format!("can't open {}: {}", path.display(), err)),
+        Err(_) => return Err("no routing key given".into()),
+    };
+    match config["routing_key"].as_str() {
+        Some(routing_key) => Ok(routing_key.into()),
+        None => Err(format!("{} has no routing_key", path.display())),
+    }
+}
+
+/// Send the event, returning its key.
+fn send(args: Args, routing_key: String) -> Result<String, ErrorResponse> {
+    let key = args.key.clone().unwrap_or_default();
+
+    if args.v2 {
+        return match args.command {
+            Command::Trigger => {
+                let mut event = v2::TriggerEvent::new(
+                    routing_key, args.description.unwrap_or_default(), args.source, args.severity
+                );
+                if let Some(key) = args.key {
+                    event = event.dedup_key(key);
+                }
+                if !args.details.is_empty() {
+                    event = event.custom_details(details(args.details)?);
+                }
+                if let Some(client) = args.client {
+                    event = event.client(client);
+                }
+                if let Some(client_url) = args.client_url {
+                    event = event.client_url(client_url);
+                }
+                if !args.links.is_empty() {
+                    let links = args.links.into_iter()
+                        .map(|link| v2::Link::new(link, None))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    event = event.links(links);
+                }
+                event.send().map(|processed| processed.dedup_key)
+            },
+            Command::Acknowledge => v2::AcknowledgeEvent::new(routing_key, key).send().map(|p| p.dedup_key),
+            Command::Resolve => v2::ResolveEvent::new(routing_key, key).send().map(|p| p.dedup_key),
+            Command::Run => unreachable!("run is handled by run::run"),
+        };
+    }
+
+    match args.command {
+        Command::Trigger => {
+            v1_trigger(args, routing_key, Details::new())?.send().map(|processed| processed.incident_key)
+        },
+        Command::Acknowledge => v1::AcknowledgeEvent::new(routing_key, key).send().map(|p| p.incident_key),
+        Command::Resolve => v1::ResolveEvent::new(routing_key, key).send().map(|p| p.incident_key),
+        Command::Run => unreachable!("run is handled by run::run"),
+    }
+}
+
+/// A v1 trigger event built from the arguments, with the `--detail`s added
+/// to `details`.
+fn v1_trigger(args: Args, routing_key: String, details: Details) -> Result<v1::TriggerEvent, ErrorResponse> {
+    let details = args.details.into_iter()
+        .fold(details, |builder, (name, value)| builder.set(name, value))
+        .build()?;
+
+    let mut event = v1::TriggerEvent::new(routing_key, args.description.unwrap_or_default());
+    if let Some(key) = args.key {
+        event = event.incident_key(key);
+    }
+    if details.as_object().map_or(false, |details| !details.is_empty()) {
+        event = event.details(details);
+    }
+    if let Some(client) = args.client {
+        event = event.client(client);
+    }
+    if let Some(client_url) = args.client_url {
+        event = event.client_url(client_url);
+    }
+    if !args.links.is_empty() {
+        let contexts = args.links.into_iter()
+            .map(|link| v1::Context::link(link, None))
+            .collect::<Result<Vec<_>, _>>()?;
+        event = event.contexts(contexts);
+    }
+    Ok(event)
+}
+
+fn details(details: Vec<(String, String)>) -> Result<Value, ErrorResponse> {
+    details.into_iter()
+        .fold(Details::new(), |builder, (name, value)| builder.set(name, value))
+        .build()
+}
+
+fn main() {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(Usage::Help) => {
+            println!("{}", USAGE);
+            return;
+        },
+        Err(Usage::Error(err)) => {
+            eprintln!("pd-event: {}\n\n{}", err, USAGE);
+            process::exit(2);
+        },
+    };
+
+    let routing_key = match routing_key(&args) {
+        Ok(routing_key) => routing_key,
+        Err(err) => {
+            eprintln!("pd-event: {}", err);
+            process::exit(2);
+        },
+    };
+
+    if args.command == Command::Run {
+        process::exit(run::run(args, routing_key));
+    }
+
+    match send(args, routing_key) {
+        Ok(key) => println!("{}", key),
+        Err(err) => {
+            eprintln!("pd-event: {}", err);
+            process::exit(1);
+        },
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, Usage> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = parse(&[
+            "trigger", "--description", "disk full", "--key", "db1/disk",
+            "--detail", "host=db1", "--detail", "query=a=b", "--link", "https://example.com",
+            "--v2", "--severity", "critical",
+        ]).unwrap();
+
+        assert_eq!(args.command, Command::Trigger);
+        assert_eq!(args.description, Some("disk full".into()));
+        assert_eq!(args.key, Some("db1/disk".into()));
+        assert_eq!(
+            args.details,
+            vec![("host".into(), "db1".into()), ("query".into(), "a=b".into())]
+        );
+        assert_eq!(args.links, vec!["https://example.com".to_string()]);
+        assert!(args.v2);
+        assert_eq!(args.severity, v2::Severity::Critical);
+        assert_eq!(args.source, "pd-event");
+
+        assert_eq!(parse(&["ack", "--key", "db1/disk"]).unwrap().command, Command::Acknowledge);
+        assert_eq!(parse(&["resolve", "--help"]), Err(Usage::Help));
+
+        let args = parse(&["run", "--timeout", "60", "--", "backup.sh", "--full"]).unwrap();
+        assert_eq!(args.command, Command::Run);
+        assert_eq!(args.timeout, Some(Duration::from_secs(60)));
+        assert_eq!(args.tail, 20);
+        assert_eq!(args.command_line, vec!["backup.sh".to_string(), "--full".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        assert_eq!(parse(&[]), Err(Usage::Error("missing command".into())));
+        assert_eq!(parse(&["page"]), Err(Usage::Error("unknown command page".into())));
+        assert_eq!(parse(&["trigger"]), Err(Usage::Error("trigger needs --description".into())));
+        assert_eq!(parse(&["resolve"]), Err(Usage::Error("ack and resolve need --key".into())));
+        assert_eq!(
+            parse(&["trigger", "--description"]),
+            Err(Usage::Error("--description needs a value".into()))
+        );
+        assert_eq!(
+            parse(&["trigger", "--detail", "host"]),
+            Err(Usage::Error("--detail host isn't NAME=VALUE".into()))
+        );
+        assert_eq!(
+            parse(&["trigger", "--severity", "fatal"]),
+            Err(Usage::Error("unknown severity fatal".into()))
+        );
+        assert_eq!(parse(&["run"]), Err(Usage::Error("run needs a command after --".into())));
+        assert_eq!(parse(&["run", "--v2", "--", "true"]), Err(Usage::Error("run only sends v1 events".into())));
+        assert_eq!(
+            parse(&["trigger", "--description", "x", "--", "true"]),
+            Err(Usage::Error("only run takes a command".into()))
+        );
+    }
+
+    #[test]
+    fn test_routing_key() {
+        let mut args = parse(&["resolve", "--key", "db1/disk", "--routing-key", "abc"]).unwrap();
+        assert_eq!(routing_key(&args), Ok("abc".into()));
+
+        let path = env::temp_dir().join(format!("pd-event-config-{}.json", process::id()));
+        ::std::fs::write(&path, r#"{"routing_key": "from-config"}"#).unwrap();
+        args.routing_key = None;
+        args.config = Some(path.clone());
+        if env::var(ROUTING_KEY_VAR).is_err() {
+            assert_eq!(routing_key(&args), Ok("from-config".into()));
+        }
+        ::std::fs::remove_file(&path).unwrap();
+    }
+}
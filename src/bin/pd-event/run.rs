@@ -0,0 +1,473 @@
+use std::collections::VecDeque;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, Command as Process, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex, mpsc};
+#[cfg(unix)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
+
+use pagersduty::events::{Details, IncidentTracker, default_client};
+use pagersduty::events::v1::{ErrorResponse, IncidentKey};
+
+use {Args, v1_trigger};
+
+const DEFAULT_STATE_DIR: &'static str = ".pagerduty-run";
+
+/// The most output kept from each stream, so the event stays well below
+/// the size limit.
+const MAX_TAIL_BYTES: usize = 16 * 1024;
+
+/// Exit status when the command timed out, as with timeout(1).
+const TIMED_OUT_EXIT: i32 = 124;
+
+/// Exit status when the command couldn't be started, as with sh(1).
+const NOT_STARTED_EXIT: i32 = 127;
+
+const POLL_INTERVAL_MS: u64 = 50;
+
+/// How long to wait for the rest of the output once the command has exited
+/// or been killed, in case something it started still holds on to it.
+const OUTPUT_GRACE_MS: u64 = 1000;
+
+
+/// How the command ended.
+#[derive(Debug)]
+enum Status {
+    Exited(ExitStatus),
+    TimedOut(Duration),
+    NotStarted(io::Error),
+}
+
+#[derive(Debug)]
+struct Execution {
+    status: Status,
+    duration: Duration,
+    stdout: String,
+    stderr: String,
+}
+
+impl Execution {
+    fn succeeded(&self) -> bool {
+        match self.status {
+            Status::Exited(status) => status.success(),
+            _ => false,
+        }
+    }
+
+    fn exit_code(&self) -> i32 {
+        match self.status {
+            Status::Exited(status) => status.code().unwrap_or(1),
+            Status::TimedOut(_) => TIMED_OUT_EXIT,
+            Status::NotStarted(_) => NOT_STARTED_EXIT,
+        }
+    }
+
+    fn description(&self, command: &str) -> String {
+        match self.status {
+            Status::Exited(status) => match status.code() {
+                Some(code) => format!("`{}` exited with status {}", command, code),
+                None => format!("`{}` was killed by a signal", command),
+            },
+            Status::TimedOut(timeout) => format!("`{}` timed out after {}s", command, timeout.as_secs()),
+            Status::NotStarted(ref err) => format!("`{}` failed to start: {}", command, err),
+        }
+    }
+}
+
+
+/// Run the command, trigger or resolve its incident, and return the exit
+/// status to exit with.
+pub fn run(mut args: Args, routing_key: String) -> i32 {
+    let command = args.command_line.join(" ");
+    let execution = execute(&args.command_line, args.timeout, args.tail);
+
+    let incident_key = args.key.clone().unwrap_or_else(|| {
+        IncidentKey::new("pd-event".into(), "run".into())
+            .label("command".into(), command.clone())
+            .key()
+    });
+
+    let result = if execution.succeeded() {
+        recover(&args, &incident_key)
+    } else {
+        if args.description.is_none() {
+            args.description = Some(execution.description(&command));
+        }
+        args.key = Some(incident_key);
+        trigger(args, routing_key, &command, &execution)
+    };
+
+    match result {
+        Ok(()) => execution.exit_code(),
+        Err(err) => {
+            eprintln!("pd-event: {}", err);
+            if execution.succeeded() { 1 } else { execution.exit_code() }
+        },
+    }
+}
+
+fn recover(args: &Args, incident_key: &str) -> Result<(), ErrorResponse> {
+    let mut tracker = IncidentTracker::open(state_path(args, incident_key)?)?;
+    tracker.recover(default_client()?, incident_key)?;
+    Ok(())
+}
+
+fn trigger(args: Args, routing_key: String, command: &str, execution: &Execution) -> Result<(), ErrorResponse> {
+    let incident_key = args.key.clone().unwrap_or_default();
+    let mut tracker = IncidentTracker::open(state_path(&args, &incident_key)?)?;
+
+    let mut details = Details::new()
+        .set("command".into(), command)
+        .set("duration_secs".into(), execution.duration.as_secs())
+        .set("stdout".into(), &execution.stdout)
+        .set("stderr".into(), &execution.stderr);
+    details = match execution.status {
+        Status::Exited(status) => details.set("exit_code".into(), status.code()),
+        Status::TimedOut(_) => details.set("timed_out".into(), true),
+        Status::NotStarted(ref err) => details.set("error".into(), err.to_string()),
+    };
+
+    let event = v1_trigger(args, routing_key, details)?.truncate_description();
+    tracker.trigger(default_client()?, event)?;
+    Ok(())
+}
+
+/// The file the incident is remembered in between runs. Each incident key
+/// has its own file so that runs of different commands finishing at the
+/// same time can't overwrite each other's state.
+fn state_path(args: &Args, incident_key: &str) -> Result<PathBuf, ErrorResponse> {
+    let dir = match args.state_dir {
+        Some(ref dir) => dir.clone(),
+        None => env::var_os("HOME").map(PathBuf::from).unwrap_or_else(env::temp_dir).join(DEFAULT_STATE_DIR),
+    };
+    fs::create_dir_all(&dir)?;
+
+    let name: String = Sha256::digest(incident_key.as_bytes()).iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    Ok(dir.join(name + ".json"))
+}
+
+/// Run `command_line`, passing its output through while keeping the last
+/// `tail` lines of each stream.
+///
+/// With a `timeout` the command runs in a process group of its own, so
+/// that once it's late it can be killed along with everything it started.
+/// That puts it outside the terminal's job control: its stdin is
+/// `/dev/null`, and interrupts sent to `pd-event` are passed on to it.
+fn execute(command_line: &[String], timeout: Option<Duration>, tail: usize) -> Execution {
+    let start = Instant::now();
+    let mut process = Process::new(&command_line[0]);
+    process.args(&command_line[1..])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if timeout.is_some() {
+        process.stdin(Stdio::null());
+        own_process_group(&mut process);
+    } else {
+        process.stdin(Stdio::inherit());
+    }
+
+    let signals = Signals::catch();
+    let mut child = match process.spawn() {
+        Ok(child) => child,
+        Err(err) => return Execution {
+            status: Status::NotStarted(err),
+            duration: start.elapsed(),
+            stdout: String::new(),
+            stderr: String::new(),
+        },
+    };
+
+    let stdout_tail = Arc::new(Mutex::new(VecDeque::new()));
+    let stderr_tail = Arc::new(Mutex::new(VecDeque::new()));
+    let (sender, receiver) = mpsc::channel();
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let (stdout_sender, tail_to) = (sender.clone(), stdout_tail.clone());
+    thread::spawn(move || {
+        capture(stdout, io::stdout(), tail, &tail_to);
+        stdout_sender.send(())
+    });
+    let stderr = child.stderr.take().expect("stderr is piped");
+    let tail_to = stderr_tail.clone();
+    thread::spawn(move || {
+        capture(stderr, io::stderr(), tail, &tail_to);
+        sender.send(())
+    });
+
+    let status = loop {
+        signals.forward(&child, timeout.is_some());
+        match child.try_wait() {
+            Ok(Some(status)) => break Status::Exited(status),
+            Ok(None) => {},
+            Err(err) => break Status::NotStarted(err),
+        }
+        if let Some(timeout) = timeout {
+            if start.elapsed() >= timeout {
+                kill_group(&mut child);
+                let _ = child.wait();
+                break Status::TimedOut(timeout);
+            }
+        }
+        thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+    };
+    let duration = start.elapsed();
+    drop(signals);
+
+    // The streams close once everything holding them has exited, which may
+    // be never if the command left a daemon running. Whatever was read by
+    // the time the grace period is up is all there is.
+    let deadline = Instant::now() + Duration::from_millis(OUTPUT_GRACE_MS);
+    for _ in 0..2 {
+        let now = Instant::now();
+        let wait = if deadline > now { deadline - now } else { Duration::from_millis(0) };
+        if receiver.recv_timeout(wait).is_err() {
+            break;
+        }
+    }
+
+    Execution {
+        status: status,
+        duration: duration,
+        stdout: render(&stdout_tail),
+        stderr: render(&stderr_tail),
+    }
+}
+
+#[cfg(unix)]
+extern "C" {
+    fn setpgid(pid: i32, pgid: i32) -> i32;
+    fn kill(pid: i32, signal: i32) -> i32;
+    fn signal(signal: i32, handler: usize) -> usize;
+}
+
+#[cfg(unix)]
+const SIGINT: i32 = 2;
+#[cfg(unix)]
+const SIGKILL: i32 = 9;
+#[cfg(unix)]
+const SIGTERM: i32 = 15;
+
+/// The last signal caught while the command was running, or 0.
+#[cfg(unix)]
+static CAUGHT: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(unix)]
+extern "C" fn catch(signal: i32) {
+    CAUGHT.store(signal as usize, Ordering::SeqCst);
+}
+
+/// SIGINT and SIGTERM caught while the command runs, so that `pd-event`
+/// outlives it to report how it ended. The previous handlers are put back
+/// when dropped.
+struct Signals {
+    #[cfg(unix)]
+    previous: Vec<(i32, usize)>,
+}
+
+#[cfg(unix)]
+impl Signals {
+    fn catch() -> Signals {
+        CAUGHT.store(0, Ordering::SeqCst);
+        let previous = [SIGINT, SIGTERM].iter()
+            .map(|&number| (number, unsafe { signal(number, catch as extern "C" fn(i32) as usize) }))
+            .collect();
+        Signals { previous: previous }
+    }
+
+    /// Pass a caught signal on to the command. A command in the terminal's
+    /// process group gets interrupts from the terminal itself, so only
+    /// SIGTERM is passed on to it.
+    fn forward(&self, child: &Child, own_group: bool) {
+        let number = CAUGHT.swap(0, Ordering::SeqCst) as i32;
+        if number == 0 {
+            return;
+        }
+        unsafe {
+            if own_group {
+                kill(-(child.id() as i32), number);
+            } else if number != SIGINT {
+                kill(child.id() as i32, number);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Signals {
+    fn drop(&mut self) {
+        for &(number, handler) in &self.previous {
+            unsafe { signal(number, handler); }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+impl Signals {
+    fn catch() -> Signals {
+        Signals {}
+    }
+
+    fn forward(&self, _: &Child, _: bool) {}
+}
+
+/// Start the command in a process group of its own, so that it can be
+/// killed along with any children it started.
+#[cfg(unix)]
+#[allow(deprecated)]
+fn own_process_group(process: &mut Process) {
+    use std::os::unix::process::CommandExt;
+
+    // `pre_exec` replaced `before_exec` in a much newer Rust.
+    unsafe {
+        process.before_exec(|| {
+            if setpgid(0, 0) == 0 { Ok(()) } else { Err(io::Error::last_os_error()) }
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn own_process_group(_: &mut Process) {}
+
+/// Kill the command's process group.
+#[cfg(unix)]
+fn kill_group(child: &mut Child) {
+    if unsafe { kill(-(child.id() as i32), SIGKILL) } != 0 {
+        let _ = child.kill();
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_group(child: &mut Child) {
+    let _ = child.kill();
+}
+
+/// Copy `reader` to `echo`, keeping its last `lines` lines in `tail`.
+fn capture<R: Read, W: Write>(reader: R, mut echo: W, lines: usize, tail: &Mutex<VecDeque<String>>) {
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {},
+        }
+        let _ = echo.write_all(&line);
+
+        if lines == 0 {
+            continue;
+        }
+        let mut tail = tail.lock().unwrap();
+        if tail.len() == lines {
+            tail.pop_front();
+        }
+        tail.push_back(String::from_utf8_lossy(&line).into_owned());
+    }
+}
+
+/// The captured lines, cut down to `MAX_TAIL_BYTES`.
+fn render(tail: &Mutex<VecDeque<String>>) -> String {
+    let mut tail: String = tail.lock().unwrap().iter().map(|line| line.as_str()).collect();
+    if tail.len() > MAX_TAIL_BYTES {
+        let mut start = tail.len() - MAX_TAIL_BYTES;
+        while !tail.is_char_boundary(start) {
+            start += 1;
+        }
+        tail = tail.split_off(start);
+    }
+    tail
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn command(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn captured<R: Read, W: Write>(reader: R, echo: W, lines: usize) -> String {
+        let tail = Mutex::new(VecDeque::new());
+        capture(reader, echo, lines, &tail);
+        render(&tail)
+    }
+
+    #[test]
+    fn test_capture() {
+        let output = (1..6).map(|n| format!("line {}\n", n)).collect::<String>();
+        let mut echo = Vec::new();
+        assert_eq!(captured(output.as_bytes(), &mut echo, 2), "line 4\nline 5\n");
+        assert_eq!(echo, output.as_bytes());
+
+        assert_eq!(captured("no newline".as_bytes(), io::sink(), 2), "no newline");
+        assert_eq!(captured(output.as_bytes(), io::sink(), 0), "");
+    }
+
+    #[test]
+    fn test_state_path() {
+        let dir = env::temp_dir().join(format!("pd-event-run-{}", ::std::process::id()));
+        let args = ::parse_args(
+            vec!["run", "--state-dir", dir.to_str().unwrap(), "--", "true"].into_iter().map(String::from)
+        ).unwrap();
+
+        let db1 = state_path(&args, "db1/disk").unwrap();
+        let db2 = state_path(&args, "db2/disk").unwrap();
+        assert!(dir.is_dir());
+        assert_eq!(db1.parent(), Some(dir.as_path()));
+        assert_ne!(db1, db2);
+        assert_eq!(db1, state_path(&args, "db1/disk").unwrap());
+
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute() {
+        let execution = execute(&command(&["sh", "-c", "echo out; echo err >&2; exit 3"]), None, 10);
+        assert!(!execution.succeeded());
+        assert_eq!(execution.exit_code(), 3);
+        assert_eq!(execution.stdout, "out\n");
+        assert_eq!(execution.stderr, "err\n");
+        assert_eq!(execution.description("check"), "`check` exited with status 3");
+
+        let execution = execute(&command(&["true"]), None, 10);
+        assert!(execution.succeeded());
+
+        let execution = execute(&command(&["sleep", "5"]), Some(Duration::from_millis(100)), 10);
+        assert!(!execution.succeeded());
+        assert_eq!(execution.exit_code(), TIMED_OUT_EXIT);
+        assert!(execution.duration < Duration::from_secs(5));
+
+        // Children holding on to the output are killed too.
+        let start = Instant::now();
+        let execution = execute(
+            &command(&["sh", "-c", "echo started; sleep 5 & sleep 5"]), Some(Duration::from_millis(100)), 10
+        );
+        assert_eq!(execution.exit_code(), TIMED_OUT_EXIT);
+        assert_eq!(execution.stdout, "started\n");
+        assert!(start.elapsed() < Duration::from_secs(3));
+
+        // Commands that may be killed don't read from the terminal.
+        let execution = execute(&command(&["cat"]), Some(Duration::from_secs(5)), 10);
+        assert!(execution.succeeded());
+
+        // A daemon left holding the output doesn't keep the run waiting.
+        let start = Instant::now();
+        let execution = execute(&command(&["sh", "-c", "echo started; sleep 5 &"]), None, 10);
+        assert!(execution.succeeded());
+        assert_eq!(execution.stdout, "started\n");
+        assert!(start.elapsed() < Duration::from_secs(3));
+
+        let execution = execute(&command(&["pd-event-no-such-command"]), None, 10);
+        assert_eq!(execution.exit_code(), NOT_STARTED_EXIT);
+        assert!(execution.description("pd-event-no-such-command").contains("failed to start"));
+    }
+}
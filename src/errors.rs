@@ -1,9 +1,86 @@
+use std::error;
+use std::fmt;
+use std::io;
 use std::result;
 
+use reqwest::{self, StatusCode};
+use serde_json;
+
+use events::v1::ErrorResponse;
+
 /// Custom Result type many `procure` methods return
 pub type Result<T> = result::Result<T, Error>;
 
 /// Custom Error type returned with `procure` [`Result`](type.Result.html)'s
 #[derive(Debug)]
 pub enum Error {
+
+    /// The underlying HTTP client failed to build or send a request.
+    Http(reqwest::Error),
+
+    /// Reading the response body failed.
+    Io(io::Error),
+
+    /// A request or response body could not be (de)serialized.
+    Json(serde_json::Error),
+
+    /// The API responded with a non-successful status code. The raw
+    /// response body is kept as-is since error objects vary by endpoint.
+    Api {
+        status: StatusCode,
+        body: String,
+    },
+
+    /// An event could not be delivered to the events endpoint.
+    Events(Box<ErrorResponse>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Http(ref err) => write!(f, "HTTP error: {}", err),
+            Error::Io(ref err) => write!(f, "I/O error: {}", err),
+            Error::Json(ref err) => write!(f, "JSON error: {}", err),
+            Error::Api { ref status, ref body } => {
+                write!(f, "API responded with {}: {}", status, body)
+            },
+            Error::Events(ref err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Http(ref err) => Some(err),
+            Error::Io(ref err) => Some(err),
+            Error::Json(ref err) => Some(err),
+            Error::Api { .. } => None,
+            Error::Events(ref err) => Some(&**err),
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Error {
+        Error::Http(err)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Error {
+        Error::Json(err)
+    }
+}
+
+impl From<ErrorResponse> for Error {
+    fn from(err: ErrorResponse) -> Error {
+        Error::Events(Box::new(err))
+    }
 }
@@ -0,0 +1,213 @@
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::thread::{self, JoinHandle};
+
+use serde_json;
+
+use events::sink::EventSink;
+use events::v1::{AcknowledgeEvent, ErrorResponse, EventProcessed, ResolveEvent, TriggerEvent};
+
+
+/// An event queued on a `BackgroundSender`.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum Event {
+    Trigger(TriggerEvent),
+    Acknowledge(AcknowledgeEvent),
+    Resolve(ResolveEvent),
+}
+
+impl Event {
+    /// Validate and send the event through `sink`, like the `send_with`
+    /// method of the event it holds.
+    pub fn send_with<S: EventSink + ?Sized>(&self, sink: &S) -> Result<EventProcessed, ErrorResponse> {
+        let valid = match *self {
+            Event::Trigger(ref event) => event.validate(),
+            Event::Acknowledge(ref event) => event.validate(),
+            Event::Resolve(ref event) => event.validate(),
+        };
+        valid.map_err(ErrorResponse::Validation)?;
+        sink.send_v1(&serde_json::to_value(self)?)
+    }
+}
+
+impl From<TriggerEvent> for Event {
+    fn from(event: TriggerEvent) -> Event {
+        Event::Trigger(event)
+    }
+}
+
+impl From<AcknowledgeEvent> for Event {
+    fn from(event: AcknowledgeEvent) -> Event {
+        Event::Acknowledge(event)
+    }
+}
+
+impl From<ResolveEvent> for Event {
+    fn from(event: ResolveEvent) -> Event {
+        Event::Resolve(event)
+    }
+}
+
+
+/// The result of sending a queued event.
+#[derive(Debug)]
+pub struct Outcome {
+    pub event: Event,
+    pub result: Result<EventProcessed, ErrorResponse>,
+}
+
+
+/// Sends events from a pool of worker threads so that callers on hot paths
+/// never block on HTTP.
+///
+/// Events are queued on a bounded channel, validated and sent one by one
+/// through the given sink. An `EventsClient` retries retryable failures as
+/// configured on its builder; pass `DefaultSink` to send where the `send`
+/// methods would, honouring `set_default_sink`. The outcome of every event
+/// is passed to a callback, or to a receiver when created with
+/// `with_receiver`.
+///
+/// Dropping the sender stops accepting events, waits for the workers to
+/// drain the queue and joins them.
+#[derive(Debug)]
+pub struct BackgroundSender {
+    sender: Option<SyncSender<Event>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl BackgroundSender {
+    /// Start `workers` threads sending events from a queue holding at most
+    /// `queue_size` events, calling `on_outcome` with the result of each.
+    pub fn new<S, F>(
+        sink: S, queue_size: usize, workers: usize, on_outcome: F
+    ) -> BackgroundSender
+        where S: EventSink + Send + Sync + 'static, F: Fn(Outcome) + Send + Sync + 'static
+    {
+        let (sender, receiver) = mpsc::sync_channel::<Event>(queue_size);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let sink = Arc::new(sink);
+        let on_outcome = Arc::new(on_outcome);
+
+        let workers = (0..workers.max(1)).map(|i| {
+            let receiver = receiver.clone();
+            let sink = sink.clone();
+            let on_outcome = on_outcome.clone();
+
+            thread::Builder::new()
+                .name(format!("pagersduty-sender-{}", i))
+                .spawn(move || loop {
+                    // Release the lock before sending so other workers can
+                    // pick up events in the meantime.
+                    let event = match receiver.lock().unwrap().recv() {
+                        Ok(event) => event,
+                        Err(_) => return,
+                    };
+                    let result = event.send_with(&*sink);
+                    on_outcome(Outcome { event: event, result: result });
+                })
+                .expect("failed to spawn event sender thread")
+        }).collect();
+
+        BackgroundSender {
+            sender: Some(sender),
+            workers: workers,
+        }
+    }
+
+    /// Like `new`, but outcomes are delivered on the returned receiver.
+    ///
+    /// Outcomes are buffered without bound, so the receiver should be
+    /// drained (or dropped, discarding them).
+    pub fn with_receiver<S>(
+        sink: S, queue_size: usize, workers: usize
+    ) -> (BackgroundSender, Receiver<Outcome>) where S: EventSink + Send + Sync + 'static {
+        let (sender, receiver) = mpsc::channel();
+        let sender = Mutex::new(sender);
+        let background = BackgroundSender::new(sink, queue_size, workers, move |outcome| {
+            let _ = sender.lock().unwrap().send(outcome);
+        });
+        (background, receiver)
+    }
+
+    /// Queue an event without blocking. If the queue is full the event is
+    /// handed back in the error.
+    pub fn send<E: Into<Event>>(&self, event: E) -> Result<(), TrySendError<Event>> {
+        self.sender.as_ref().unwrap().try_send(event.into())
+    }
+
+    /// Queue an event, waiting for room in the queue if it is full.
+    pub fn send_blocking<E: Into<Event>>(&self, event: E) {
+        // The workers only exit once the sender is dropped, so this can't
+        // fail while `self` is alive.
+        self.sender.as_ref().unwrap().send(event.into()).unwrap();
+    }
+}
+
+impl Drop for BackgroundSender {
+    fn drop(&mut self) {
+        self.sender.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use events::client::EventsClient;
+    use events::sink::CaptureSink;
+
+    #[test]
+    fn test_outcomes_are_reported() {
+        let client = EventsClient::builder()
+            .url("http://127.0.0.1:9".into())
+            .max_retries(0)
+            .build()
+            .unwrap();
+        let (sender, outcomes) = BackgroundSender::with_receiver(client, 4, 2);
+
+        sender.send_blocking(TriggerEvent::new("key".into(), "down".into()));
+        sender.send_blocking(ResolveEvent::new("key".into(), "incident".into()));
+        drop(sender);
+
+        let mut outcomes: Vec<Outcome> = outcomes.iter().collect();
+        assert_eq!(outcomes.len(), 2);
+        outcomes.sort_by_key(|outcome| match outcome.event {
+            Event::Resolve(_) => 1,
+            _ => 0,
+        });
+        assert_eq!(
+            outcomes[0].event,
+            Event::Trigger(TriggerEvent::new("key".into(), "down".into()))
+        );
+        assert_eq!(
+            outcomes[1].event,
+            Event::Resolve(ResolveEvent::new("key".into(), "incident".into()))
+        );
+        assert!(outcomes.iter().all(|outcome| outcome.result.is_err()));
+    }
+
+    #[test]
+    fn test_events_are_validated() {
+        let sink = Arc::new(CaptureSink::new());
+        let (sender, outcomes) = BackgroundSender::with_receiver(sink.clone(), 4, 1);
+
+        sender.send_blocking(ResolveEvent::new("key".into(), "".into()));
+        sender.send_blocking(ResolveEvent::new("key".into(), "incident".into()));
+        drop(sender);
+
+        let outcomes: Vec<Outcome> = outcomes.iter().collect();
+        match outcomes[0].result {
+            Err(ErrorResponse::Validation(_)) => {},
+            ref result => panic!("expected a validation error, got {:?}", result),
+        }
+        assert!(outcomes[1].result.is_ok());
+        assert_eq!(sink.len(), 1);
+        sink.assert_resolved("incident");
+    }
+}
@@ -0,0 +1,287 @@
+use std::cmp;
+use std::io::Read;
+use std::str;
+use std::thread;
+use std::time::Duration;
+
+use reqwest::{self, StatusCode};
+use reqwest::header::UserAgent;
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json;
+
+use events::v1::{self, ErrorResponse, InvalidEventBody};
+use events::v2;
+
+static EVENTS_URL: &'static str = "https://events.pagerduty.com";
+
+static V1_PATH: &'static str = "generic/2010-04-15/create_event.json";
+
+static V2_PATH: &'static str = "v2/enqueue";
+
+static CHANGE_PATH: &'static str = "v2/change/enqueue";
+
+static USER_AGENT: &'static str = concat!("pagersduty/", env!("CARGO_PKG_VERSION"));
+
+const DEFAULT_MAX_RETRIES: u32 = 3;
+
+const DEFAULT_RETRY_BACKOFF_MS: u64 = 500;
+
+/// The longest wait between retries, however long the back off or the
+/// endpoint's `Retry-After` is.
+const MAX_RETRY_DELAY_SECS: u64 = 60;
+
+lazy_static! {
+    static ref DEFAULT_CLIENT: Result<EventsClient, String> = EventsClient::new()
+        .map_err(|err| format!("failed to create default events client: {}", err));
+}
+
+
+/// The shared client used by the `send` methods of the event types.
+pub fn default_client() -> Result<&'static EventsClient, ErrorResponse> {
+    match *DEFAULT_CLIENT {
+        Ok(ref client) => Ok(client),
+        Err(ref err) => Err(ErrorResponse::Unexpected(err.clone())),
+    }
+}
+
+
+/// A client for the PagerDuty events endpoints.
+///
+/// The client holds a connection pool, so it should be created once and
+/// reused rather than created per event.
+#[derive(Debug)]
+pub struct EventsClient {
+    http_client: reqwest::Client,
+    url: String,
+    user_agent: String,
+    max_retries: u32,
+    retry_backoff: Duration,
+}
+
+impl EventsClient {
+    /// A client using the default endpoint URL, timeout and user agent.
+    pub fn new() -> Result<EventsClient, ErrorResponse> {
+        EventsClientBuilder::new().build()
+    }
+
+    pub fn builder() -> EventsClientBuilder {
+        EventsClientBuilder::new()
+    }
+
+    /// Send an event to the v1 `create_event.json` endpoint.
+    pub fn send_v1<T: Serialize>(&self, event: T) -> Result<v1::EventProcessed, ErrorResponse> {
+        self.post(V1_PATH, event)
+    }
+
+    /// Send an event to the Events API v2 `enqueue` endpoint.
+    pub fn send_v2<T: Serialize>(&self, event: T) -> Result<v2::EventProcessed, ErrorResponse> {
+        self.post(V2_PATH, event)
+    }
+
+    /// Send a change event to the Change Events API endpoint.
+    pub fn send_change<T: Serialize>(
+        &self, event: T
+    ) -> Result<v2::ChangeEventProcessed, ErrorResponse> {
+        self.post(CHANGE_PATH, event)
+    }
+
+    /// Post `event`, retrying up to `max_retries` times while the failure
+    /// is retryable.
+    fn post<T: Serialize, R: DeserializeOwned>(&self, path: &str, event: T) -> Result<R, ErrorResponse> {
+        let url = format!("{}/{}", self.url, path);
+
+        let mut attempt = 0;
+        loop {
+            match self.post_once(&url, &event) {
+                Err(ref err) if err.is_retryable() && attempt < self.max_retries => {
+                    thread::sleep(self.retry_delay(attempt, err));
+                    attempt += 1;
+                },
+                result => return result,
+            }
+        }
+    }
+
+    fn post_once<T: Serialize, R: DeserializeOwned>(&self, url: &str, event: &T) -> Result<R, ErrorResponse> {
+        let mut response = self.http_client
+            .post(url)?
+            .header(UserAgent::new(self.user_agent.clone()))
+            .json(event)?
+            .send()?;
+
+        let status = response.status();
+
+        // The v1 endpoint rate limits with 403, the v2 endpoints with 429.
+        if status == StatusCode::Forbidden || status == StatusCode::TooManyRequests {
+            let retry_after = response.headers()
+                .get_raw("Retry-After")
+                .and_then(|raw| raw.one())
+                .and_then(|value| str::from_utf8(value).ok())
+                .and_then(|value| value.trim().parse().ok())
+                .map(Duration::from_secs);
+            return Err(ErrorResponse::RateLimited { retry_after: retry_after });
+        }
+
+        let mut output = String::new();
+        response.read_to_string(&mut output)?;
+
+        match status {
+            // The v1 endpoint answers with 200, the v2 endpoints with 202.
+            StatusCode::Ok | StatusCode::Accepted => {
+                Ok(serde_json::from_str(&output)?)
+            },
+            StatusCode::BadRequest => {
+                Err(InvalidEventBody::parse(&output).into())
+            },
+            _ if status.is_server_error() => {
+                Err(ErrorResponse::ServerError { status: status, body: output })
+            },
+            _ => Err(ErrorResponse::Unexpected(output)),
+        }
+    }
+
+    /// How long to wait before retry number `attempt + 1`: whatever the
+    /// endpoint asked for, otherwise an exponential back off, but never more
+    /// than a minute.
+    fn retry_delay(&self, attempt: u32, err: &ErrorResponse) -> Duration {
+        let max_delay = Duration::from_secs(MAX_RETRY_DELAY_SECS);
+        let delay = match *err {
+            ErrorResponse::RateLimited { retry_after: Some(retry_after) } => Some(retry_after),
+            _ => self.retry_backoff.checked_mul(2u32.pow(cmp::min(attempt, 16))),
+        };
+        delay.map_or(max_delay, |delay| cmp::min(delay, max_delay))
+    }
+}
+
+
+/// Configures and builds an [`EventsClient`](struct.EventsClient.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventsClientBuilder {
+    url: String,
+    timeout: Option<Duration>,
+    user_agent: String,
+    max_retries: u32,
+    retry_backoff: Duration,
+}
+
+impl EventsClientBuilder {
+    pub fn new() -> EventsClientBuilder {
+        EventsClientBuilder {
+            url: EVENTS_URL.into(),
+            timeout: None,
+            user_agent: USER_AGENT.into(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_backoff: Duration::from_millis(DEFAULT_RETRY_BACKOFF_MS),
+        }
+    }
+
+    /// The base URL events are sent to, without a trailing slash. Defaults
+    /// to `https://events.pagerduty.com`; point it elsewhere to send events
+    /// to a local test double.
+    pub fn url(mut self, url: String) -> EventsClientBuilder {
+        self.url = url;
+        self
+    }
+
+    /// The total timeout of each request, including connecting and
+    /// reading the response.
+    pub fn timeout(mut self, timeout: Duration) -> EventsClientBuilder {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// The `User-Agent` sent with each request. Defaults to
+    /// `pagersduty/<version>`.
+    pub fn user_agent(mut self, user_agent: String) -> EventsClientBuilder {
+        self.user_agent = user_agent;
+        self
+    }
+
+    /// How many times an event is resent after a retryable failure, i.e.
+    /// rate limiting, a server error or a connection failure. Defaults to 3;
+    /// set to 0 to disable retries.
+    pub fn max_retries(mut self, max_retries: u32) -> EventsClientBuilder {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// The delay before the first retry, doubled for each further retry.
+    /// Ignored when a rate limited response says how long to wait. Either
+    /// way no retry waits longer than a minute. Defaults to 500ms.
+    pub fn retry_backoff(mut self, retry_backoff: Duration) -> EventsClientBuilder {
+        self.retry_backoff = retry_backoff;
+        self
+    }
+
+    pub fn build(self) -> Result<EventsClient, ErrorResponse> {
+        let mut builder = reqwest::Client::builder()?;
+        if let Some(timeout) = self.timeout {
+            builder.timeout(timeout);
+        }
+        let http_client = builder.build()?;
+
+        Ok(EventsClient {
+            http_client: http_client,
+            url: self.url.trim_end_matches('/').into(),
+            user_agent: self.user_agent,
+            max_retries: self.max_retries,
+            retry_backoff: self.retry_backoff,
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let client = EventsClient::builder()
+            .url("http://localhost:8080/".into())
+            .timeout(Duration::from_secs(5))
+            .user_agent("my-monitor/1.0".into())
+            .build()
+            .unwrap();
+
+        assert_eq!(client.url, "http://localhost:8080");
+        assert_eq!(client.user_agent, "my-monitor/1.0");
+
+        let client = EventsClient::new().unwrap();
+        assert_eq!(client.url, EVENTS_URL);
+        assert_eq!(client.user_agent, USER_AGENT);
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let client = EventsClient::builder()
+            .retry_backoff(Duration::from_millis(100))
+            .build()
+            .unwrap();
+
+        let server_error = ErrorResponse::ServerError {
+            status: StatusCode::BadGateway,
+            body: "".into(),
+        };
+        assert_eq!(client.retry_delay(0, &server_error), Duration::from_millis(100));
+        assert_eq!(client.retry_delay(2, &server_error), Duration::from_millis(400));
+
+        let rate_limited = ErrorResponse::RateLimited {
+            retry_after: Some(Duration::from_secs(30)),
+        };
+        assert_eq!(client.retry_delay(0, &rate_limited), Duration::from_secs(30));
+
+        let rate_limited = ErrorResponse::RateLimited {
+            retry_after: Some(Duration::from_secs(86400)),
+        };
+        assert_eq!(client.retry_delay(0, &rate_limited), Duration::from_secs(MAX_RETRY_DELAY_SECS));
+        assert_eq!(client.retry_delay(16, &server_error), Duration::from_secs(MAX_RETRY_DELAY_SECS));
+
+        let client = EventsClient::builder()
+            .retry_backoff(Duration::from_secs(u64::max_value() / 2))
+            .build()
+            .unwrap();
+        assert_eq!(client.retry_delay(3, &server_error), Duration::from_secs(MAX_RETRY_DELAY_SECS));
+    }
+}
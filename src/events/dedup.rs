@@ -0,0 +1,323 @@
+use std::cmp;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use serde_json::{self, Value};
+
+use events::background::Event;
+use events::v1::TriggerEvent;
+
+const DEFAULT_FLAP_THRESHOLD: usize = 5;
+
+const DEFAULT_FLAP_WINDOW_SECS: u64 = 15 * 60;
+
+
+/// An optional layer in front of a sender that drops redundant events.
+///
+/// An event identical to the last one let through for the same incident
+/// key within `window` is suppressed. When an incident key changes between
+/// triggered and resolved `flap_threshold` times within `flap_window`, it
+/// is considered flapping: a single summarized trigger event is emitted and
+/// every further event for that key is suppressed until it has been stable
+/// for `flap_window`. The last event suppressed while flapping, e.g. the
+/// final resolve, is then returned by `poll`, which should be called
+/// periodically so that incidents recovering while flapping get resolved.
+#[derive(Debug)]
+pub struct Deduplicator {
+    window: Duration,
+    flap_threshold: usize,
+    flap_window: Duration,
+    keys: HashMap<String, KeyState>,
+}
+
+#[derive(Debug)]
+struct KeyState {
+    /// The last event let through and when.
+    last: Option<(Value, Instant)>,
+
+    /// When any event for the key was last seen.
+    last_seen: Instant,
+
+    /// Whether the key was last triggered or resolved.
+    last_type: Option<String>,
+
+    /// When the key changed between triggered and resolved.
+    changes: VecDeque<Instant>,
+
+    /// The service key and description of the last trigger event, used
+    /// for the summarized event.
+    trigger: Option<(String, String)>,
+
+    flapping: bool,
+
+    /// The last event suppressed while flapping, the current state to send
+    /// once the key stops flapping.
+    suppressed: Option<Event>,
+}
+
+impl Deduplicator {
+    /// Suppress identical events within `window`, with the default flap
+    /// detection of 5 state changes within 15 minutes.
+    pub fn new(window: Duration) -> Deduplicator {
+        Deduplicator {
+            window: window,
+            flap_threshold: DEFAULT_FLAP_THRESHOLD,
+            flap_window: Duration::from_secs(DEFAULT_FLAP_WINDOW_SECS),
+            keys: HashMap::new(),
+        }
+    }
+
+    /// How many changes between triggered and resolved within the flap
+    /// window mark an incident key as flapping. Zero disables flap
+    /// detection.
+    pub fn flap_threshold(mut self, flap_threshold: usize) -> Deduplicator {
+        self.flap_threshold = flap_threshold;
+        self
+    }
+
+    pub fn flap_window(mut self, flap_window: Duration) -> Deduplicator {
+        self.flap_window = flap_window;
+        self
+    }
+
+    /// The event to send in place of `event`, or `None` if it should be
+    /// suppressed.
+    pub fn filter<E: Into<Event>>(&mut self, event: E) -> Option<Event> {
+        self.filter_at(event.into(), Instant::now())
+    }
+
+    /// The last event suppressed for each incident key that has stopped
+    /// flapping since the last call.
+    pub fn poll(&mut self) -> Vec<Event> {
+        self.poll_at(Instant::now())
+    }
+
+    fn poll_at(&mut self, now: Instant) -> Vec<Event> {
+        let flap_window = self.flap_window;
+        let mut events = Vec::new();
+        for state in self.keys.values_mut() {
+            if state.stop_flapping(now, flap_window) {
+                events.extend(state.suppressed.take());
+            }
+        }
+        self.expire(now);
+        events
+    }
+
+    /// Forget keys not seen for a while, except those still flapping whose
+    /// last suppressed event hasn't been returned by `poll`.
+    fn expire(&mut self, now: Instant) {
+        let expiry = cmp::max(self.window, self.flap_window);
+        self.keys.retain(|_, state| {
+            now.duration_since(state.last_seen) < expiry || state.flapping
+        });
+    }
+
+    fn filter_at(&mut self, event: Event, now: Instant) -> Option<Event> {
+        let value = serde_json::to_value(&event).expect("events always serialize");
+        let event_type = value["event_type"].as_str().unwrap_or_default().to_owned();
+
+        // Trigger events without an incident key each open a new incident,
+        // so they can only be deduplicated against identical events.
+        let key = match value["incident_key"].as_str() {
+            Some(key) => key.to_owned(),
+            None => value.to_string(),
+        };
+
+        let window = self.window;
+        let flap_threshold = self.flap_threshold;
+        let flap_window = self.flap_window;
+
+        self.expire(now);
+
+        let state = self.keys.entry(key.clone()).or_insert_with(|| KeyState {
+            last: None,
+            last_seen: now,
+            last_type: None,
+            changes: VecDeque::new(),
+            trigger: None,
+            flapping: false,
+            suppressed: None,
+        });
+        state.last_seen = now;
+
+        // This event is the current state, so whatever was suppressed
+        // while flapping doesn't need sending any more.
+        if state.stop_flapping(now, flap_window) {
+            state.suppressed = None;
+        }
+
+        if event_type == "trigger" || event_type == "resolve" {
+            if state.last_type.as_ref().map_or(false, |last| *last != event_type) {
+                state.changes.push_back(now);
+            }
+            state.last_type = Some(event_type.clone());
+        }
+        if event_type == "trigger" {
+            state.trigger = Some((
+                value["service_key"].as_str().unwrap_or_default().to_owned(),
+                value["description"].as_str().unwrap_or_default().to_owned(),
+            ));
+        }
+
+        if !state.flapping && flap_threshold > 0 && state.changes.len() >= flap_threshold {
+            state.flapping = true;
+            state.last = None;
+
+            let (service_key, description) = state.trigger.clone().unwrap_or_else(|| {
+                (value["service_key"].as_str().unwrap_or_default().to_owned(), key.clone())
+            });
+            let summary = TriggerEvent::new(
+                service_key,
+                format!(
+                    "Flapping: {} ({} state changes in {}s)",
+                    description, state.changes.len(), flap_window.as_secs()
+                ),
+            )
+                .incident_key(key)
+                .details(json!({
+                    "flapping": true,
+                    "state_changes": state.changes.len(),
+                    "flap_window_secs": flap_window.as_secs(),
+                }));
+            // The event itself is held back like any other while flapping,
+            // so that `poll` can still send it once the flapping stops.
+            state.suppressed = Some(event);
+            return Some(Event::Trigger(summary));
+        }
+
+        if state.flapping {
+            state.suppressed = Some(event);
+            return None;
+        }
+
+        if let Some((ref last, at)) = state.last {
+            if *last == value && now.duration_since(at) < window {
+                return None;
+            }
+        }
+
+        state.last = Some((value, now));
+        Some(event)
+    }
+}
+
+impl KeyState {
+    /// Forget state changes older than `flap_window`, returning whether the
+    /// key stopped flapping because of it.
+    fn stop_flapping(&mut self, now: Instant, flap_window: Duration) -> bool {
+        while self.changes.front().map_or(false, |t| now.duration_since(*t) >= flap_window) {
+            self.changes.pop_front();
+        }
+        if self.flapping && self.changes.is_empty() {
+            self.flapping = false;
+            return true;
+        }
+        false
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use events::v1::ResolveEvent;
+
+    fn trigger() -> TriggerEvent {
+        TriggerEvent::new("service".into(), "disk full".into()).incident_key("db1/disk".into())
+    }
+
+    fn resolve() -> ResolveEvent {
+        ResolveEvent::new("service".into(), "db1/disk".into())
+    }
+
+    #[test]
+    fn test_identical_events_are_suppressed() {
+        let mut dedup = Deduplicator::new(Duration::from_secs(60));
+        let start = Instant::now();
+
+        assert_eq!(dedup.filter_at(trigger().into(), start), Some(Event::Trigger(trigger())));
+        assert_eq!(dedup.filter_at(trigger().into(), start + Duration::from_secs(30)), None);
+
+        let other = TriggerEvent::new("service".into(), "disk still full".into())
+            .incident_key("db1/disk".into());
+        assert!(dedup.filter_at(other.into(), start + Duration::from_secs(40)).is_some());
+
+        assert!(dedup.filter_at(trigger().into(), start + Duration::from_secs(50)).is_some());
+        assert!(dedup.filter_at(trigger().into(), start + Duration::from_secs(111)).is_some());
+    }
+
+    #[test]
+    fn test_flapping_is_summarized() {
+        let mut dedup = Deduplicator::new(Duration::from_secs(60))
+            .flap_threshold(3)
+            .flap_window(Duration::from_secs(100));
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert!(dedup.filter_at(trigger().into(), at(0)).is_some());
+        assert!(dedup.filter_at(resolve().into(), at(1)).is_some());
+        assert!(dedup.filter_at(trigger().into(), at(2)).is_some());
+
+        let summary = TriggerEvent::new(
+            "service".into(),
+            "Flapping: disk full (3 state changes in 100s)".into(),
+        )
+            .incident_key("db1/disk".into())
+            .details(json!({"flapping": true, "state_changes": 3, "flap_window_secs": 100}));
+        assert_eq!(dedup.filter_at(resolve().into(), at(3)), Some(Event::Trigger(summary)));
+
+        assert_eq!(dedup.filter_at(trigger().into(), at(4)), None);
+        assert_eq!(dedup.filter_at(resolve().into(), at(5)), None);
+
+        // Stable for the flap window, so events flow again.
+        assert_eq!(dedup.filter_at(resolve().into(), at(106)), Some(Event::Resolve(resolve())));
+    }
+
+    #[test]
+    fn test_flapping_ending_on_resolve_is_resolved() {
+        let mut dedup = Deduplicator::new(Duration::from_secs(60))
+            .flap_threshold(3)
+            .flap_window(Duration::from_secs(100));
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert!(dedup.filter_at(trigger().into(), at(0)).is_some());
+        assert!(dedup.filter_at(resolve().into(), at(1)).is_some());
+        assert!(dedup.filter_at(trigger().into(), at(2)).is_some());
+        assert!(dedup.filter_at(resolve().into(), at(3)).is_some());
+        assert_eq!(dedup.filter_at(trigger().into(), at(4)), None);
+        assert_eq!(dedup.filter_at(resolve().into(), at(5)), None);
+
+        assert_eq!(dedup.poll_at(at(50)), vec![]);
+
+        // Other keys expiring doesn't lose the suppressed resolve.
+        let other = TriggerEvent::new("service".into(), "cpu hot".into()).incident_key("db1/cpu".into());
+        assert!(dedup.filter_at(other.into(), at(300)).is_some());
+
+        assert_eq!(dedup.poll_at(at(300)), vec![Event::Resolve(resolve())]);
+        assert_eq!(dedup.poll_at(at(301)), vec![]);
+    }
+
+    #[test]
+    fn test_flapping_starting_on_resolve_is_resolved() {
+        let mut dedup = Deduplicator::new(Duration::from_secs(60))
+            .flap_threshold(3)
+            .flap_window(Duration::from_secs(100));
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert!(dedup.filter_at(trigger().into(), at(0)).is_some());
+        assert!(dedup.filter_at(resolve().into(), at(1)).is_some());
+        assert!(dedup.filter_at(trigger().into(), at(2)).is_some());
+        match dedup.filter_at(resolve().into(), at(3)) {
+            Some(Event::Trigger(_)) => {},
+            other => panic!("expected the flapping summary, got {:?}", other),
+        }
+
+        assert_eq!(dedup.poll_at(at(50)), vec![]);
+        assert_eq!(dedup.poll_at(at(104)), vec![Event::Resolve(resolve())]);
+        assert_eq!(dedup.poll_at(at(105)), vec![]);
+    }
+}
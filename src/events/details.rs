@@ -0,0 +1,179 @@
+use std::collections::BTreeSet;
+
+use serde::ser::{Error, Serialize};
+use serde_json::{self, Map, Value};
+
+use events::v1::{ErrorResponse, MAX_EVENT_SIZE, Violation};
+
+static REDACTED: &'static str = "[REDACTED]";
+
+
+/// Builds the `details` object of a `TriggerEvent` from any serializable
+/// values.
+///
+/// Values are merged in the order given, later keys replacing earlier ones.
+/// Keys configured with `redact` have their values replaced wherever they
+/// appear, at any depth and ignoring case. Errors serializing values are
+/// reported by `build`.
+#[derive(Debug, Default)]
+pub struct Details {
+    fields: Map<String, Value>,
+    redact: BTreeSet<String>,
+    error: Option<serde_json::Error>,
+}
+
+impl Details {
+    pub fn new() -> Details {
+        Details::default()
+    }
+
+    /// Set `key` to `value`.
+    pub fn set<T: Serialize>(mut self, key: String, value: T) -> Details {
+        match serde_json::to_value(value) {
+            Ok(value) => {
+                self.fields.insert(key, value);
+            },
+            Err(err) => self.fail(err),
+        }
+        self
+    }
+
+    /// Merge in the fields of `value`, which must serialize to a JSON
+    /// object, such as a struct or map.
+    pub fn merge<T: Serialize>(mut self, value: &T) -> Details {
+        match serde_json::to_value(value) {
+            Ok(Value::Object(fields)) => self.fields.extend(fields),
+            Ok(_) => self.fail(serde_json::Error::custom("details must serialize to an object")),
+            Err(err) => self.fail(err),
+        }
+        self
+    }
+
+    /// Replace the value of every field named `key` with a placeholder.
+    pub fn redact(mut self, key: String) -> Details {
+        self.redact.insert(key.to_lowercase());
+        self
+    }
+
+    /// The details object, or the first error serializing a value. Details
+    /// too large to fit in an event are rejected.
+    pub fn build(self) -> Result<Value, ErrorResponse> {
+        if let Some(err) = self.error {
+            return Err(err.into());
+        }
+
+        let mut details = Value::Object(self.fields);
+        redact(&mut details, &self.redact);
+
+        let size = serde_json::to_vec(&details)?.len();
+        if size > MAX_EVENT_SIZE {
+            return Err(ErrorResponse::Validation(vec![Violation::EventTooLarge { size: size }]));
+        }
+
+        Ok(details)
+    }
+
+    fn fail(&mut self, err: serde_json::Error) {
+        if self.error.is_none() {
+            self.error = Some(err);
+        }
+    }
+}
+
+fn redact(value: &mut Value, keys: &BTreeSet<String>) {
+    if keys.is_empty() {
+        return;
+    }
+
+    match *value {
+        Value::Object(ref mut fields) => {
+            for (key, value) in fields.iter_mut() {
+                if keys.contains(&key.to_lowercase()) {
+                    *value = Value::String(REDACTED.into());
+                } else {
+                    redact(value, keys);
+                }
+            }
+        },
+        Value::Array(ref mut values) => {
+            for value in values.iter_mut() {
+                redact(value, keys);
+            }
+        },
+        _ => {},
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct CheckResult {
+        check: String,
+        used_percent: f64,
+        connection: Connection,
+    }
+
+    #[derive(Serialize)]
+    struct Connection {
+        url: String,
+        #[serde(rename = "Password")]
+        password: String,
+    }
+
+    #[test]
+    fn test_build() {
+        let result = CheckResult {
+            check: "disk_usage".into(),
+            used_percent: 97.5,
+            connection: Connection {
+                url: "postgres://db1".into(),
+                password: "hunter2".into(),
+            },
+        };
+        let mut labels = BTreeMap::new();
+        labels.insert("env", "prod");
+        labels.insert("check", "overridden");
+
+        let details = Details::new()
+            .set("host".into(), "db1")
+            .merge(&result)
+            .merge(&labels)
+            .set("tokens".into(), vec![json!({"token": "abc"})])
+            .redact("password".into())
+            .redact("token".into())
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            details,
+            json!({
+                "host": "db1",
+                "check": "overridden",
+                "used_percent": 97.5,
+                "connection": {"url": "postgres://db1", "Password": "[REDACTED]"},
+                "env": "prod",
+                "tokens": [{"token": "[REDACTED]"}],
+            })
+        );
+    }
+
+    #[test]
+    fn test_build_errors() {
+        let err = Details::new().merge(&vec![1, 2]).set("a".into(), 1).build().unwrap_err();
+        assert_eq!(err.to_string(), "JSON error: details must serialize to an object");
+
+        let err = Details::new().set("log".into(), "x".repeat(MAX_EVENT_SIZE)).build().unwrap_err();
+        match err {
+            ErrorResponse::Validation(ref violations) => match violations[..] {
+                [Violation::EventTooLarge { size }] => assert!(size > MAX_EVENT_SIZE),
+                _ => panic!("unexpected violations: {:?}", violations),
+            },
+            _ => panic!("unexpected error: {:?}", err),
+        }
+    }
+}
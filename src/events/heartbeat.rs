@@ -0,0 +1,267 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use events::sink::EventSink;
+use events::v1::{ErrorResponse, EventProcessed, ResolveEvent, TriggerEvent};
+
+/// How long to wait before resending an event that failed.
+const RETRY_INTERVAL_SECS: u64 = 30;
+
+
+/// A dead man's switch for long-running jobs.
+///
+/// The job calls `tick` regularly. If no tick arrives within the deadline,
+/// a watchdog thread triggers an incident, and resolves it once ticks
+/// resume. Events that fail to send are retried every 30 seconds. Dropping
+/// the heartbeat stops the watchdog without resolving an open incident.
+pub struct Heartbeat {
+    shared: Arc<Shared>,
+    watchdog: Option<JoinHandle<()>>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+struct State {
+    last_tick: Instant,
+
+    /// When the open incident was triggered, if there is one.
+    triggered_at: Option<Instant>,
+
+    /// When to resend after a failure.
+    retry_at: Option<Instant>,
+
+    stopped: bool,
+}
+
+enum Action {
+    Trigger,
+    Resolve,
+}
+
+impl Heartbeat {
+    pub fn builder(service_key: String, incident_key: String, deadline: Duration) -> HeartbeatBuilder {
+        HeartbeatBuilder::new(service_key, incident_key, deadline)
+    }
+
+    /// Record that the job is alive.
+    pub fn tick(&self) {
+        self.shared.state.lock().unwrap().last_tick = Instant::now();
+        self.shared.changed.notify_one();
+    }
+
+    /// Whether an incident has been triggered for a missed deadline and not
+    /// yet resolved.
+    pub fn is_triggered(&self) -> bool {
+        self.shared.state.lock().unwrap().triggered_at.is_some()
+    }
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().stopped = true;
+        self.shared.changed.notify_one();
+        if let Some(watchdog) = self.watchdog.take() {
+            let _ = watchdog.join();
+        }
+    }
+}
+
+
+pub struct HeartbeatBuilder {
+    service_key: String,
+    incident_key: String,
+    deadline: Duration,
+    description: Option<String>,
+    sink: Option<Arc<dyn EventSink + Send + Sync>>,
+}
+
+impl HeartbeatBuilder {
+    /// Trigger `incident_key` on `service_key` when no tick arrives within
+    /// `deadline`.
+    pub fn new(service_key: String, incident_key: String, deadline: Duration) -> HeartbeatBuilder {
+        HeartbeatBuilder {
+            service_key: service_key,
+            incident_key: incident_key,
+            deadline: deadline,
+            description: None,
+            sink: None,
+        }
+    }
+
+    /// The description of the incident. Defaults to saying which heartbeat
+    /// missed its deadline.
+    pub fn description(mut self, description: String) -> HeartbeatBuilder {
+        self.description = Some(description);
+        self
+    }
+
+    /// Send events through `sink` instead of the default used by
+    /// `TriggerEvent::send`.
+    pub fn sink(mut self, sink: Arc<dyn EventSink + Send + Sync>) -> HeartbeatBuilder {
+        self.sink = Some(sink);
+        self
+    }
+
+    /// Start the watchdog. The deadline starts counting now.
+    pub fn start(self) -> Heartbeat {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                last_tick: Instant::now(),
+                triggered_at: None,
+                retry_at: None,
+                stopped: false,
+            }),
+            changed: Condvar::new(),
+        });
+
+        let watchdog_shared = shared.clone();
+        let watchdog = thread::Builder::new()
+            .name("pagersduty-heartbeat".into())
+            .spawn(move || self.watch(&watchdog_shared))
+            .expect("failed to spawn heartbeat thread");
+
+        Heartbeat {
+            shared: shared,
+            watchdog: Some(watchdog),
+        }
+    }
+
+    fn watch(self, shared: &Shared) {
+        loop {
+            let action = {
+                let mut state = shared.state.lock().unwrap();
+                loop {
+                    if state.stopped {
+                        return;
+                    }
+
+                    let now = Instant::now();
+                    let since_tick = now.duration_since(state.last_tick);
+                    let action = match state.triggered_at {
+                        Some(triggered_at) if state.last_tick > triggered_at => Some(Action::Resolve),
+                        None if since_tick >= self.deadline => Some(Action::Trigger),
+                        _ => None,
+                    };
+                    let retry_wait = state.retry_at
+                        .and_then(|at| if at > now { Some(at - now) } else { None });
+
+                    state = match (action, retry_wait) {
+                        (Some(action), None) => break action,
+                        (_, Some(wait)) => shared.changed.wait_timeout(state, wait).unwrap().0,
+                        (None, None) if state.triggered_at.is_some() => shared.changed.wait(state).unwrap(),
+                        (None, None) => {
+                            shared.changed.wait_timeout(state, self.deadline - since_tick).unwrap().0
+                        },
+                    };
+                }
+            };
+
+            let sent_at = Instant::now();
+            let result = match action {
+                Action::Trigger => self.send_trigger(),
+                Action::Resolve => self.send_resolve(),
+            };
+
+            let mut state = shared.state.lock().unwrap();
+            match result {
+                Ok(_) => {
+                    state.triggered_at = match action {
+                        Action::Trigger => Some(sent_at),
+                        Action::Resolve => None,
+                    };
+                    state.retry_at = None;
+                },
+                Err(_) => {
+                    state.retry_at = Some(sent_at + Duration::from_secs(RETRY_INTERVAL_SECS));
+                },
+            }
+        }
+    }
+
+    fn send_trigger(&self) -> Result<EventProcessed, ErrorResponse> {
+        let description = self.description.clone().unwrap_or_else(|| format!(
+            "{} missed its heartbeat deadline of {}", self.incident_key, format_deadline(self.deadline)
+        ));
+        let deadline_ms = self.deadline.as_secs() * 1000 + u64::from(self.deadline.subsec_millis());
+        let event = TriggerEvent::new(self.service_key.clone(), description)
+            .incident_key(self.incident_key.clone())
+            .details(json!({"deadline_ms": deadline_ms}));
+
+        match self.sink {
+            Some(ref sink) => event.send_with(&**sink),
+            None => event.send(),
+        }
+    }
+
+    fn send_resolve(&self) -> Result<EventProcessed, ErrorResponse> {
+        let event = ResolveEvent::new(self.service_key.clone(), self.incident_key.clone());
+        match self.sink {
+            Some(ref sink) => event.send_with(&**sink),
+            None => event.send(),
+        }
+    }
+}
+
+/// The deadline in seconds, with milliseconds if it has any.
+fn format_deadline(deadline: Duration) -> String {
+    match deadline.subsec_millis() {
+        0 => format!("{}s", deadline.as_secs()),
+        millis => format!("{}.{:03}s", deadline.as_secs(), millis),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use events::sink::CaptureSink;
+
+    fn wait_for<F: Fn() -> bool>(condition: F) {
+        let start = Instant::now();
+        while !condition() && start.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_heartbeat() {
+        let sink = Arc::new(CaptureSink::new());
+        let heartbeat = Heartbeat::builder("key".into(), "nightly-backup".into(), Duration::from_millis(100))
+            .sink(sink.clone())
+            .start();
+
+        heartbeat.tick();
+        assert!(sink.is_empty());
+
+        wait_for(|| heartbeat.is_triggered());
+        assert!(heartbeat.is_triggered());
+        assert_eq!(sink.len(), 1);
+        sink.assert_triggered("nightly-backup");
+        assert_eq!(
+            sink.events()[0].1["description"],
+            "nightly-backup missed its heartbeat deadline of 0.100s"
+        );
+        assert_eq!(sink.events()[0].1["details"]["deadline_ms"], 100);
+
+        heartbeat.tick();
+        wait_for(|| !heartbeat.is_triggered());
+        assert!(!heartbeat.is_triggered());
+        assert_eq!(sink.len(), 2);
+        sink.assert_resolved("nightly-backup");
+
+        drop(heartbeat);
+    }
+
+    #[test]
+    fn test_format_deadline() {
+        assert_eq!(format_deadline(Duration::from_secs(90)), "90s");
+        assert_eq!(format_deadline(Duration::from_millis(1500)), "1.500s");
+        assert_eq!(format_deadline(Duration::from_millis(5)), "0.005s");
+    }
+}
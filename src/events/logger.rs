@@ -0,0 +1,213 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{Level, Log, Metadata, Record};
+
+use events::pager::{Pager, is_sending, message_template};
+use events::sink::EventSink;
+use events::v1::{IncidentKey, TriggerEvent};
+
+const DEFAULT_RATE_LIMIT_SECS: u64 = 5 * 60;
+
+
+/// A `log::Log` that passes every record to another logger and pages on
+/// the ones that matter.
+///
+/// Records at or above the configured level, or whose target starts with
+/// one of the configured prefixes, are sent as trigger events from a
+/// background thread. Records are grouped into incidents by target and
+/// message template, i.e. the message with its numbers masked, and each
+/// incident is paged at most once per rate limit window. Pages that don't
+/// fit in the queue are dropped rather than blocking the caller.
+pub struct PagingLogger<L> {
+    inner: L,
+    service_key: String,
+    level: Level,
+    targets: Vec<String>,
+    pager: Pager,
+}
+
+impl<L: Log> PagingLogger<L> {
+    pub fn builder(service_key: String) -> PagingLoggerBuilder {
+        PagingLoggerBuilder::new(service_key)
+    }
+
+    fn pages(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level ||
+            self.targets.iter().any(|target| metadata.target().starts_with(target.as_str()))
+    }
+
+    fn page(&self, record: &Record) {
+        let message = record.args().to_string();
+        let incident_key = IncidentKey::new(self.service_key.clone(), record.target().into())
+            .label("template".into(), message_template(&message))
+            .key();
+
+        let event = TriggerEvent::new(
+            self.service_key.clone(),
+            format!("[{}] {}: {}", record.level(), record.target(), message),
+        )
+            .incident_key(incident_key.clone())
+            .details(json!({
+                "level": record.level().to_string(),
+                "target": record.target(),
+                "message": message,
+                "module_path": record.module_path(),
+                "file": record.file(),
+                "line": record.line(),
+            }))
+            .truncate_description();
+
+        self.pager.page(&incident_key, event);
+    }
+}
+
+impl<L: Log> Log for PagingLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata) || self.pages(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.inner.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+        if self.pages(record.metadata()) && !is_sending() {
+            self.page(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+
+pub struct PagingLoggerBuilder {
+    service_key: String,
+    level: Level,
+    targets: Vec<String>,
+    rate_limit: Duration,
+    sink: Option<Arc<dyn EventSink + Send + Sync>>,
+}
+
+impl PagingLoggerBuilder {
+    /// A builder paging on `Error` records at most once every 5 minutes
+    /// per incident.
+    pub fn new(service_key: String) -> PagingLoggerBuilder {
+        PagingLoggerBuilder {
+            service_key: service_key,
+            level: Level::Error,
+            targets: vec![],
+            rate_limit: Duration::from_secs(DEFAULT_RATE_LIMIT_SECS),
+            sink: None,
+        }
+    }
+
+    /// Page on records at or above `level`.
+    pub fn level(mut self, level: Level) -> PagingLoggerBuilder {
+        self.level = level;
+        self
+    }
+
+    /// Page on every record whose target starts with `target`, whatever
+    /// its level.
+    pub fn target(mut self, target: String) -> PagingLoggerBuilder {
+        self.targets.push(target);
+        self
+    }
+
+    /// The minimum time between pages for the same incident.
+    pub fn rate_limit(mut self, rate_limit: Duration) -> PagingLoggerBuilder {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    /// Send pages through `sink` instead of the default used by
+    /// `TriggerEvent::send`.
+    pub fn sink(mut self, sink: Arc<dyn EventSink + Send + Sync>) -> PagingLoggerBuilder {
+        self.sink = Some(sink);
+        self
+    }
+
+    /// Wrap `inner`, starting the thread that sends pages.
+    pub fn build<L: Log>(self, inner: L) -> PagingLogger<L> {
+        PagingLogger {
+            inner: inner,
+            service_key: self.service_key,
+            level: self.level,
+            targets: self.targets,
+            pager: Pager::new(self.sink, self.rate_limit),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use log::LevelFilter;
+    use std::thread;
+    use std::time::Instant;
+
+    use events::sink::CaptureSink;
+
+    struct NullLogger;
+
+    impl Log for NullLogger {
+        fn enabled(&self, metadata: &Metadata) -> bool {
+            metadata.level() <= LevelFilter::Info
+        }
+
+        fn log(&self, _: &Record) {}
+
+        fn flush(&self) {}
+    }
+
+    fn log(logger: &PagingLogger<NullLogger>, level: Level, target: &str, message: &str) {
+        logger.log(
+            &Record::builder()
+                .level(level)
+                .target(target)
+                .args(format_args!("{}", message))
+                .build()
+        );
+    }
+
+    #[test]
+    fn test_paging_logger() {
+        let sink = Arc::new(CaptureSink::new());
+        let logger = PagingLogger::<NullLogger>::builder("key".into())
+            .target("billing::".into())
+            .sink(sink.clone())
+            .build(NullLogger);
+
+        log(&logger, Level::Error, "db", "connection to 10.0.0.12 failed");
+        log(&logger, Level::Error, "db", "connection to 10.0.0.13 failed");
+        log(&logger, Level::Warn, "db", "slow query");
+        log(&logger, Level::Warn, "billing::charge", "charge failed");
+        log(&logger, Level::Error, "db", "disk full");
+
+        let start = Instant::now();
+        while sink.len() < 3 && start.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let descriptions: Vec<String> = sink.events().iter()
+            .map(|&(_, ref event)| event["description"].as_str().unwrap().to_owned())
+            .collect();
+        assert_eq!(
+            descriptions,
+            vec![
+                "[ERROR] db: connection to 10.0.0.12 failed",
+                "[WARN] billing::charge: charge failed",
+                "[ERROR] db: disk full",
+            ]
+        );
+
+        let key = IncidentKey::new("key".into(), "db".into())
+            .label("template".into(), "disk full".into())
+            .key();
+        sink.assert_triggered(&key);
+    }
+}
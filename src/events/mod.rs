@@ -1 +1,33 @@
+pub mod background;
+pub mod client;
+pub mod dedup;
+pub mod details;
+pub mod heartbeat;
+#[cfg(feature = "log")]
+pub mod logger;
+pub mod outbox;
+#[cfg(any(feature = "log", feature = "tracing"))]
+mod pager;
+#[cfg(feature = "panic-hook")]
+pub mod panic_hook;
+pub mod sink;
+pub mod tracker;
+#[cfg(feature = "tracing")]
+pub mod tracing_layer;
 pub mod v1;
+pub mod v2;
+
+pub use events::background::BackgroundSender;
+pub use events::client::{EventsClient, EventsClientBuilder, default_client};
+pub use events::dedup::Deduplicator;
+pub use events::details::Details;
+pub use events::heartbeat::Heartbeat;
+#[cfg(feature = "log")]
+pub use events::logger::PagingLogger;
+pub use events::outbox::{Outbox, OutboxSink};
+#[cfg(feature = "panic-hook")]
+pub use events::panic_hook::{PanicHookConfig, install_panic_hook};
+pub use events::sink::{CaptureSink, DefaultSink, EventSink, JsonLinesSink, StdoutSink, clear_default_sink, set_default_sink};
+pub use events::tracker::IncidentTracker;
+#[cfg(feature = "tracing")]
+pub use events::tracing_layer::PagingLayer;
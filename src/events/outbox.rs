@@ -0,0 +1,414 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::ser::Serialize;
+use serde_json::{self, Value};
+
+use events::sink::{Endpoint, EventSink};
+use events::v1::{self, ErrorResponse};
+use events::v2;
+
+
+/// A line of the spool file.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Record {
+    Add {
+        id: u64,
+        endpoint: Endpoint,
+        event: Value,
+    },
+    Done {
+        id: u64,
+    },
+}
+
+
+/// A durable outbox that spools events to an append-only file so they
+/// survive network partitions and process restarts.
+///
+/// Each event is written to the spool file before it is sent and marked as
+/// done once the events endpoint has processed it. Events that fail with a
+/// retryable error (see `ErrorResponse::is_retryable`) stay in the spool
+/// and are resent by `replay`, typically called on startup. Events that can
+/// never succeed, such as invalid events, are dropped from the spool and
+/// their error returned.
+///
+/// To spool every event sent with `send`, turn the outbox into a sink with
+/// `into_sink` and pass it to `set_default_sink`.
+#[derive(Debug)]
+pub struct Outbox {
+    path: PathBuf,
+    file: File,
+    next_id: u64,
+    pending: BTreeMap<u64, (Endpoint, Value)>,
+}
+
+impl Outbox {
+    /// Open the spool file at `path`, creating it if needed, and load any
+    /// events left pending by a previous process.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Outbox, ErrorResponse> {
+        let path = path.as_ref().to_path_buf();
+        let pending = Outbox::load(&path)?;
+        let next_id = pending.keys().next_back().map_or(0, |id| id + 1);
+
+        // Rewrite the spool with only the pending events so it doesn't
+        // grow without bound across restarts.
+        let tmp_path = path.with_extension("tmp");
+        {
+            let mut tmp = File::create(&tmp_path)?;
+            for (id, &(endpoint, ref event)) in &pending {
+                write_record(&mut tmp, &Record::Add {
+                    id: *id,
+                    endpoint: endpoint,
+                    event: event.clone(),
+                })?;
+            }
+            tmp.sync_all()?;
+        }
+        fs::rename(&tmp_path, &path)?;
+
+        let file = OpenOptions::new().append(true).open(&path)?;
+
+        Ok(Outbox {
+            path: path,
+            file: file,
+            next_id: next_id,
+            pending: pending,
+        })
+    }
+
+    /// The path of the spool file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The number of events spooled but not yet processed.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Spool and send an event to the v1 `create_event.json` endpoint.
+    pub fn send_v1<S: EventSink + ?Sized, T: Serialize>(
+        &mut self, sink: &S, event: &T
+    ) -> Result<v1::EventProcessed, ErrorResponse> {
+        let event = serde_json::to_value(event)?;
+        let id = self.add(Endpoint::V1, event.clone())?;
+        let result = sink.send_v1(&event);
+        self.settle(id, result)
+    }
+
+    /// Spool and send an event to the Events API v2 `enqueue` endpoint.
+    pub fn send_v2<S: EventSink + ?Sized, T: Serialize>(
+        &mut self, sink: &S, event: &T
+    ) -> Result<v2::EventProcessed, ErrorResponse> {
+        let event = serde_json::to_value(event)?;
+        let id = self.add(Endpoint::V2, event.clone())?;
+        let result = sink.send_v2(&event);
+        self.settle(id, result)
+    }
+
+    /// Spool and send a change event to the Change Events API endpoint.
+    pub fn send_change<S: EventSink + ?Sized, T: Serialize>(
+        &mut self, sink: &S, event: &T
+    ) -> Result<v2::ChangeEventProcessed, ErrorResponse> {
+        let event = serde_json::to_value(event)?;
+        let id = self.add(Endpoint::Change, event.clone())?;
+        let result = sink.send_change(&event);
+        self.settle(id, result)
+    }
+
+    /// Resend every pending event, oldest first, returning how many were
+    /// processed. Stops at the first retryable failure so that events are
+    /// delivered in order; non-retryable failures are dropped.
+    pub fn replay<S: EventSink + ?Sized>(&mut self, sink: &S) -> Result<usize, ErrorResponse> {
+        let pending: Vec<(u64, Endpoint, Value)> = self.pending.iter()
+            .map(|(id, &(endpoint, ref event))| (*id, endpoint, event.clone()))
+            .collect();
+
+        let mut processed = 0;
+        for (id, endpoint, event) in pending {
+            let result = match endpoint {
+                Endpoint::V1 => sink.send_v1(&event).map(|_| ()),
+                Endpoint::V2 => sink.send_v2(&event).map(|_| ()),
+                Endpoint::Change => sink.send_change(&event).map(|_| ()),
+            };
+            match self.settle(id, result) {
+                Ok(()) => processed += 1,
+                Err(ref err) if !err.is_retryable() => continue,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(processed)
+    }
+
+    /// A sink spooling every event before sending it through `sink`.
+    pub fn into_sink(self, sink: Arc<dyn EventSink + Send + Sync>) -> OutboxSink {
+        OutboxSink {
+            outbox: Mutex::new(self),
+            sink: sink,
+        }
+    }
+
+    fn load(path: &Path) -> Result<BTreeMap<u64, (Endpoint, Value)>, ErrorResponse> {
+        let mut pending = BTreeMap::new();
+
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(ref err) if err.kind() == ::std::io::ErrorKind::NotFound => return Ok(pending),
+            Err(err) => return Err(err.into()),
+        };
+
+        for line in BufReader::new(file).split(b'\n') {
+            // A partially written last line is left behind if the process
+            // died mid-write, possibly cut inside a character; that event
+            // was never sent so it's skipped.
+            match serde_json::from_slice(&line?) {
+                Ok(Record::Add { id, endpoint, event }) => {
+                    pending.insert(id, (endpoint, event));
+                },
+                Ok(Record::Done { id }) => {
+                    pending.remove(&id);
+                },
+                Err(_) => continue,
+            }
+        }
+
+        Ok(pending)
+    }
+
+    fn add(&mut self, endpoint: Endpoint, event: Value) -> Result<u64, ErrorResponse> {
+        let id = self.next_id;
+        write_record(&mut self.file, &Record::Add {
+            id: id,
+            endpoint: endpoint,
+            event: event.clone(),
+        })?;
+        self.file.sync_data()?;
+
+        self.next_id += 1;
+        self.pending.insert(id, (endpoint, event));
+        Ok(id)
+    }
+
+    fn done(&mut self, id: u64) -> Result<(), ErrorResponse> {
+        self.pending.remove(&id);
+
+        if self.pending.is_empty() {
+            // Nothing left to replay, so the whole spool can go.
+            self.file.set_len(0)?;
+        } else {
+            write_record(&mut self.file, &Record::Done { id: id })?;
+        }
+        self.file.sync_data()?;
+        Ok(())
+    }
+
+    /// Record the outcome of sending event `id`, keeping it spooled only if
+    /// resending may succeed.
+    fn settle<R>(&mut self, id: u64, result: Result<R, ErrorResponse>) -> Result<R, ErrorResponse> {
+        match result {
+            Err(ref err) if err.is_retryable() => {},
+            _ => self.done(id)?,
+        }
+        result
+    }
+}
+
+/// An `EventSink` that spools events in an `Outbox` before passing them on
+/// to another sink. Events are sent one at a time, in order.
+pub struct OutboxSink {
+    outbox: Mutex<Outbox>,
+    sink: Arc<dyn EventSink + Send + Sync>,
+}
+
+impl OutboxSink {
+    /// The number of events spooled but not yet processed.
+    pub fn pending(&self) -> usize {
+        self.outbox.lock().unwrap().pending()
+    }
+
+    /// Resend every pending event, see `Outbox::replay`.
+    pub fn replay(&self) -> Result<usize, ErrorResponse> {
+        self.outbox.lock().unwrap().replay(&*self.sink)
+    }
+}
+
+impl EventSink for OutboxSink {
+    fn send_v1(&self, event: &Value) -> Result<v1::EventProcessed, ErrorResponse> {
+        self.outbox.lock().unwrap().send_v1(&*self.sink, event)
+    }
+
+    fn send_v2(&self, event: &Value) -> Result<v2::EventProcessed, ErrorResponse> {
+        self.outbox.lock().unwrap().send_v2(&*self.sink, event)
+    }
+
+    fn send_change(&self, event: &Value) -> Result<v2::ChangeEventProcessed, ErrorResponse> {
+        self.outbox.lock().unwrap().send_change(&*self.sink, event)
+    }
+}
+
+fn write_record<W: Write>(writer: &mut W, record: &Record) -> Result<(), ErrorResponse> {
+    let mut line = serde_json::to_vec(record)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::io::{self, Write};
+    use std::process;
+
+    use events::sink::CaptureSink;
+
+    /// A sink that can't reach PagerDuty.
+    struct UnreachableSink;
+
+    impl EventSink for UnreachableSink {
+        fn send_v1(&self, _: &Value) -> Result<v1::EventProcessed, ErrorResponse> {
+            Err(io::Error::new(io::ErrorKind::ConnectionReset, "connection reset").into())
+        }
+
+        fn send_v2(&self, _: &Value) -> Result<v2::EventProcessed, ErrorResponse> {
+            Err(io::Error::new(io::ErrorKind::ConnectionReset, "connection reset").into())
+        }
+
+        fn send_change(&self, _: &Value) -> Result<v2::ChangeEventProcessed, ErrorResponse> {
+            Err(io::Error::new(io::ErrorKind::ConnectionReset, "connection reset").into())
+        }
+    }
+
+    fn spool_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(
+            format!("pagersduty-outbox-{}-{}.jsonl", name, process::id())
+        );
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_pending_events_survive_reopening() {
+        let path = spool_path("reopen");
+
+        {
+            let mut outbox = Outbox::open(&path).unwrap();
+            outbox.add(Endpoint::V1, json!({"event_type": "trigger"})).unwrap();
+            outbox.add(Endpoint::V2, json!({"event_action": "trigger"})).unwrap();
+            outbox.add(Endpoint::Change, json!({"payload": {}})).unwrap();
+            outbox.done(1).unwrap();
+        }
+
+        let mut outbox = Outbox::open(&path).unwrap();
+        assert_eq!(outbox.pending(), 2);
+        assert_eq!(
+            outbox.pending.get(&0),
+            Some(&(Endpoint::V1, json!({"event_type": "trigger"})))
+        );
+        assert_eq!(outbox.add(Endpoint::V1, json!({})).unwrap(), 3);
+
+        outbox.done(0).unwrap();
+        outbox.done(2).unwrap();
+        outbox.done(3).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().len(), 0);
+
+        let outbox = Outbox::open(&path).unwrap();
+        assert_eq!(outbox.pending(), 0);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_partial_lines_are_skipped() {
+        let path = spool_path("partial");
+
+        {
+            let mut file = File::create(&path).unwrap();
+            write_record(&mut file, &Record::Add {
+                id: 4,
+                endpoint: Endpoint::V2,
+                event: json!({"event_action": "resolve"}),
+            }).unwrap();
+            file.write_all(b"{\"op\":\"add\",\"id\":5,\"endp").unwrap();
+        }
+
+        let outbox = Outbox::open(&path).unwrap();
+        assert_eq!(outbox.pending(), 1);
+        assert_eq!(outbox.next_id, 5);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_lines_cut_inside_a_character_are_skipped() {
+        let path = spool_path("multibyte");
+
+        {
+            let mut file = File::create(&path).unwrap();
+            write_record(&mut file, &Record::Add {
+                id: 0,
+                endpoint: Endpoint::V1,
+                event: json!({"description": "caf\u{e9} on fire"}),
+            }).unwrap();
+            // The first byte of "\u{e9}", without the second.
+            file.write_all(b"{\"op\":\"add\",\"id\":1,\"endpoint\":\"v1\",\"event\":{\"description\":\"caf\xc3").unwrap();
+        }
+
+        let outbox = Outbox::open(&path).unwrap();
+        assert_eq!(outbox.pending(), 1);
+        assert_eq!(
+            outbox.pending.get(&0),
+            Some(&(Endpoint::V1, json!({"description": "caf\u{e9} on fire"})))
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_failed_events_are_replayed() {
+        let path = spool_path("replay");
+        let event = v1::TriggerEvent::new("key".into(), "disk full".into())
+            .incident_key("db1/disk".into());
+
+        let mut outbox = Outbox::open(&path).unwrap();
+        assert!(outbox.send_v1(&UnreachableSink, &event).unwrap_err().is_retryable());
+        assert_eq!(outbox.pending(), 1);
+
+        let sink = CaptureSink::new();
+        assert_eq!(outbox.replay(&sink).unwrap(), 1);
+        assert_eq!(outbox.pending(), 0);
+        sink.assert_triggered("db1/disk");
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_outbox_sink() {
+        let path = spool_path("sink");
+        let capture = Arc::new(CaptureSink::new());
+
+        let sink = Outbox::open(&path).unwrap().into_sink(Arc::new(UnreachableSink));
+        v1::ResolveEvent::new("key".into(), "db1/disk".into()).send_with(&sink).unwrap_err();
+        assert_eq!(sink.pending(), 1);
+        drop(sink);
+
+        let sink = Outbox::open(&path).unwrap().into_sink(capture.clone());
+        assert_eq!(sink.pending(), 1);
+        assert_eq!(sink.replay().unwrap(), 1);
+        v1::ResolveEvent::new("key".into(), "db2/disk".into()).send_with(&sink).unwrap();
+        assert_eq!(sink.pending(), 0);
+        capture.assert_resolved("db1/disk");
+        capture.assert_resolved("db2/disk");
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,180 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, SyncSender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use events::sink::EventSink;
+use events::v1::TriggerEvent;
+
+const QUEUE_SIZE: usize = 64;
+
+thread_local! {
+    /// Set on the thread sending pages, so that anything logged while
+    /// sending (e.g. by the HTTP client) can't page again.
+    static SENDING: Cell<bool> = Cell::new(false);
+}
+
+
+/// Whether the current thread is the one sending pages.
+pub(crate) fn is_sending() -> bool {
+    SENDING.with(|sending| sending.get())
+}
+
+
+/// Sends pages from a background thread for the logging integrations,
+/// paging each incident at most once per `rate_limit`. Pages that don't
+/// fit in the queue are dropped rather than blocking the caller.
+pub(crate) struct Pager {
+    rate_limit: Duration,
+    paged: Mutex<HashMap<String, Instant>>,
+    sender: Mutex<SyncSender<TriggerEvent>>,
+}
+
+impl Pager {
+    /// Start the sending thread, sending through `sink` or, if `None`, the
+    /// default used by `TriggerEvent::send`.
+    pub fn new(sink: Option<Arc<dyn EventSink + Send + Sync>>, rate_limit: Duration) -> Pager {
+        let (sender, receiver) = mpsc::sync_channel::<TriggerEvent>(QUEUE_SIZE);
+
+        thread::Builder::new()
+            .name("pagersduty-pager".into())
+            .spawn(move || {
+                SENDING.with(|sending| sending.set(true));
+                for event in receiver {
+                    // There's nowhere to report failures without risking
+                    // a loop, so they're dropped.
+                    let _ = match sink {
+                        Some(ref sink) => event.send_with(&**sink),
+                        None => event.send(),
+                    };
+                }
+            })
+            .expect("failed to spawn pager thread");
+
+        Pager {
+            rate_limit: rate_limit,
+            paged: Mutex::new(HashMap::new()),
+            sender: Mutex::new(sender),
+        }
+    }
+
+    /// Queue `event` unless `incident_key` was paged within the rate limit.
+    /// A page dropped because the queue is full doesn't count towards it.
+    pub fn page(&self, incident_key: &str, event: TriggerEvent) {
+        let now = Instant::now();
+        let rate_limit = self.rate_limit;
+        let mut paged = self.paged.lock().unwrap();
+        paged.retain(|_, at| now.duration_since(*at) < rate_limit);
+        if paged.contains_key(incident_key) {
+            return;
+        }
+
+        if self.sender.lock().unwrap().try_send(event).is_ok() {
+            paged.insert(incident_key.into(), now);
+        }
+    }
+}
+
+
+/// `message` with every run of digits replaced by `#`, so that messages
+/// logged from the same format string are grouped together.
+pub(crate) fn message_template(message: &str) -> String {
+    let mut template = String::with_capacity(message.len());
+    let mut in_number = false;
+    for c in message.chars() {
+        if c.is_ascii_digit() {
+            if !in_number {
+                template.push('#');
+            }
+            in_number = true;
+        } else {
+            template.push(c);
+            in_number = false;
+        }
+    }
+    template
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use serde_json::Value;
+
+    use events::sink::CaptureSink;
+    use events::v1::{self, ErrorResponse};
+    use events::v2;
+
+    /// A sink that doesn't send anything until `gate` is unlocked.
+    #[derive(Default)]
+    struct GatedSink {
+        gate: Mutex<()>,
+        sent: CaptureSink,
+    }
+
+    impl EventSink for GatedSink {
+        fn send_v1(&self, event: &Value) -> Result<v1::EventProcessed, ErrorResponse> {
+            let _gate = self.gate.lock().unwrap();
+            self.sent.send_v1(event)
+        }
+
+        fn send_v2(&self, event: &Value) -> Result<v2::EventProcessed, ErrorResponse> {
+            let _gate = self.gate.lock().unwrap();
+            self.sent.send_v2(event)
+        }
+
+        fn send_change(&self, event: &Value) -> Result<v2::ChangeEventProcessed, ErrorResponse> {
+            let _gate = self.gate.lock().unwrap();
+            self.sent.send_change(event)
+        }
+    }
+
+    fn page(pager: &Pager, key: &str) {
+        pager.page(key, TriggerEvent::new("service".into(), "disk full".into()).incident_key(key.into()));
+    }
+
+    fn wait_for<F: Fn() -> bool>(done: F) {
+        let start = Instant::now();
+        while !done() {
+            assert!(start.elapsed() < Duration::from_secs(5), "timed out");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_dropped_pages_are_not_rate_limited() {
+        let sink = Arc::new(GatedSink::default());
+        let pager = Pager::new(Some(sink.clone()), Duration::from_secs(300));
+
+        {
+            let _gate = sink.gate.lock().unwrap();
+            // However many the sending thread has taken, the queue is full
+            // once the last of these has been tried.
+            for n in 0..QUEUE_SIZE + 1 {
+                page(&pager, &format!("db{}/disk", n));
+            }
+            page(&pager, "web1/disk");
+        }
+
+        wait_for(|| sink.sent.len() >= QUEUE_SIZE);
+        page(&pager, "web1/disk");
+        wait_for(|| sink.sent.events().iter().any(|&(_, ref event)| event["incident_key"] == "web1/disk"));
+
+        let sent = sink.sent.len();
+        page(&pager, "web1/disk");
+        page(&pager, "db0/disk");
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(sink.sent.len(), sent);
+    }
+
+    #[test]
+    fn test_message_template() {
+        assert_eq!(
+            message_template("connection to 10.0.0.12 failed after 3 retries"),
+            "connection to #.#.#.# failed after # retries"
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use std::any::Any;
+use std::cell::Cell;
+use std::panic;
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::Duration;
+
+use backtrace::Backtrace;
+
+use events::client::EventsClient;
+use events::sink::{EventSink, default_sink};
+use events::v1::{ErrorResponse, IncidentKey, TriggerEvent};
+
+const DEFAULT_TIMEOUT_SECS: u64 = 5;
+
+thread_local! {
+    /// Set on a thread while it pages a panic, so that a panic while
+    /// sending doesn't page again. Panics on other threads still page.
+    static PAGING: Cell<bool> = Cell::new(false);
+}
+
+
+/// Configures the hook installed by `install_panic_hook`.
+pub struct PanicHookConfig {
+    service_key: String,
+    incident_key: Option<String>,
+    timeout: Duration,
+    sink: Option<Arc<dyn EventSink + Send + Sync>>,
+}
+
+impl PanicHookConfig {
+    /// Page `service_key`, waiting at most 5 seconds for the event to be
+    /// sent.
+    pub fn new(service_key: String) -> PanicHookConfig {
+        PanicHookConfig {
+            service_key: service_key,
+            incident_key: None,
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            sink: None,
+        }
+    }
+
+    /// Group every panic into one incident. By default panics are grouped
+    /// by where they happened.
+    pub fn incident_key(mut self, incident_key: String) -> PanicHookConfig {
+        self.incident_key = Some(incident_key);
+        self
+    }
+
+    /// How long to wait for the event to be sent, through whichever sink,
+    /// before letting the panic continue. The event isn't retried.
+    pub fn timeout(mut self, timeout: Duration) -> PanicHookConfig {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Send the event through `sink` instead of an `EventsClient`.
+    pub fn sink(mut self, sink: Arc<dyn EventSink + Send + Sync>) -> PanicHookConfig {
+        self.sink = Some(sink);
+        self
+    }
+}
+
+
+/// Install a panic hook that sends a trigger event with the panic message,
+/// location and backtrace before the panic continues. The hook that was
+/// installed before, e.g. the one printing the panic, runs first.
+///
+/// The event is sent through the configured sink, otherwise the default
+/// sink set when the panic happens, otherwise a client. Whichever it is,
+/// the panic continues once the configured timeout is up.
+pub fn install_panic_hook(config: PanicHookConfig) -> Result<(), ErrorResponse> {
+    let client: Arc<dyn EventSink + Send + Sync> = Arc::new(
+        EventsClient::builder()
+            .timeout(config.timeout)
+            .max_retries(0)
+            .build()?
+    );
+
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        previous(info);
+
+        if PAGING.with(|paging| paging.replace(true)) {
+            return;
+        }
+        let message = payload_message(info.payload());
+        let location = info.location()
+            .map(|location| format!("{}:{}:{}", location.file(), location.line(), location.column()))
+            .unwrap_or_else(|| "unknown location".into());
+
+        let event = panic_event(&config, message, location);
+        let sink = config.sink.clone().or_else(default_sink).unwrap_or_else(|| client.clone());
+        send(event, sink, config.timeout);
+        PAGING.with(|paging| paging.set(false));
+    }));
+
+    Ok(())
+}
+
+/// Send `event` from another thread, waiting for it at most `timeout`.
+/// There's nothing useful to do with an error while panicking, so errors
+/// are dropped.
+fn send(event: TriggerEvent, sink: Arc<dyn EventSink + Send + Sync>, timeout: Duration) {
+    let (sender, receiver) = mpsc::channel();
+    let sending = thread::Builder::new()
+        .name("pagersduty-panic-hook".into())
+        .spawn(move || {
+            PAGING.with(|paging| paging.set(true));
+            let _ = event.send_with(&*sink);
+            let _ = sender.send(());
+        });
+    if sending.is_ok() {
+        let _ = receiver.recv_timeout(timeout);
+    }
+}
+
+fn panic_event(config: &PanicHookConfig, message: String, location: String) -> TriggerEvent {
+    let thread = thread::current();
+    let thread = thread.name().unwrap_or("<unnamed>");
+
+    let incident_key = match config.incident_key {
+        Some(ref incident_key) => incident_key.clone(),
+        None => IncidentKey::new(config.service_key.clone(), "panic".into())
+            .label("location".into(), location.clone())
+            .key(),
+    };
+
+    TriggerEvent::new(
+        config.service_key.clone(),
+        format!("thread '{}' panicked at '{}', {}", thread, message, location),
+    )
+        .incident_key(incident_key)
+        .details(json!({
+            "message": message,
+            "location": location,
+            "thread": thread,
+            "backtrace": format!("{:?}", Backtrace::new()),
+        }))
+        .truncate_description()
+}
+
+/// The message passed to `panic!`, if it was a string.
+fn payload_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).into()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<Any>".into()
+    }
+}
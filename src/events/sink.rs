@@ -0,0 +1,332 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+
+use serde_json::{self, Value};
+use sha2::{Digest, Sha256};
+
+use events::client::EventsClient;
+use events::v1::{self, ErrorResponse};
+use events::v2;
+
+lazy_static! {
+    static ref DEFAULT_SINK: RwLock<Option<Arc<dyn EventSink + Send + Sync>>> = RwLock::new(None);
+}
+
+
+/// Send events through `sink` instead of the default client, e.g. to keep
+/// local development and CI from paging anyone. Affects every `send`
+/// method and function that doesn't take a client.
+pub fn set_default_sink(sink: Arc<dyn EventSink + Send + Sync>) {
+    *DEFAULT_SINK.write().unwrap() = Some(sink);
+}
+
+/// Send events through the default client again.
+pub fn clear_default_sink() {
+    *DEFAULT_SINK.write().unwrap() = None;
+}
+
+/// The sink set with `set_default_sink`, if any.
+pub(crate) fn default_sink() -> Option<Arc<dyn EventSink + Send + Sync>> {
+    DEFAULT_SINK.read().unwrap().clone()
+}
+
+
+/// The endpoint an event is destined for.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Endpoint {
+    V1,
+    V2,
+    Change,
+}
+
+
+/// A destination for serialized events.
+///
+/// `EventsClient` sends events to PagerDuty; the other sinks record them
+/// locally and respond as if PagerDuty had processed them.
+pub trait EventSink {
+    fn send_v1(&self, event: &Value) -> Result<v1::EventProcessed, ErrorResponse>;
+
+    fn send_v2(&self, event: &Value) -> Result<v2::EventProcessed, ErrorResponse>;
+
+    fn send_change(&self, event: &Value) -> Result<v2::ChangeEventProcessed, ErrorResponse>;
+}
+
+impl EventSink for EventsClient {
+    fn send_v1(&self, event: &Value) -> Result<v1::EventProcessed, ErrorResponse> {
+        EventsClient::send_v1(self, event)
+    }
+
+    fn send_v2(&self, event: &Value) -> Result<v2::EventProcessed, ErrorResponse> {
+        EventsClient::send_v2(self, event)
+    }
+
+    fn send_change(&self, event: &Value) -> Result<v2::ChangeEventProcessed, ErrorResponse> {
+        EventsClient::send_change(self, event)
+    }
+}
+
+impl<T: EventSink + ?Sized> EventSink for Arc<T> {
+    fn send_v1(&self, event: &Value) -> Result<v1::EventProcessed, ErrorResponse> {
+        (**self).send_v1(event)
+    }
+
+    fn send_v2(&self, event: &Value) -> Result<v2::EventProcessed, ErrorResponse> {
+        (**self).send_v2(event)
+    }
+
+    fn send_change(&self, event: &Value) -> Result<v2::ChangeEventProcessed, ErrorResponse> {
+        (**self).send_change(event)
+    }
+}
+
+
+/// Sends events where the `send` methods do: through the sink set with
+/// `set_default_sink`, otherwise the shared default client.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultSink;
+
+impl EventSink for DefaultSink {
+    fn send_v1(&self, event: &Value) -> Result<v1::EventProcessed, ErrorResponse> {
+        v1::send(event)
+    }
+
+    fn send_v2(&self, event: &Value) -> Result<v2::EventProcessed, ErrorResponse> {
+        v2::send(event)
+    }
+
+    fn send_change(&self, event: &Value) -> Result<v2::ChangeEventProcessed, ErrorResponse> {
+        v2::send_change(event)
+    }
+}
+
+/// Implements `EventSink` for a local sink in terms of a method recording
+/// each event.
+macro_rules! local_sink {
+    ($sink:ty) => {
+        impl EventSink for $sink {
+            fn send_v1(&self, event: &Value) -> Result<v1::EventProcessed, ErrorResponse> {
+                self.record(Endpoint::V1, event)?;
+                Ok(v1::EventProcessed {
+                    status: "success".into(),
+                    message: "Event processed".into(),
+                    incident_key: event_key(event, "incident_key"),
+                })
+            }
+
+            fn send_v2(&self, event: &Value) -> Result<v2::EventProcessed, ErrorResponse> {
+                self.record(Endpoint::V2, event)?;
+                Ok(v2::EventProcessed {
+                    status: "success".into(),
+                    message: "Event processed".into(),
+                    dedup_key: event_key(event, "dedup_key"),
+                })
+            }
+
+            fn send_change(&self, event: &Value) -> Result<v2::ChangeEventProcessed, ErrorResponse> {
+                self.record(Endpoint::Change, event)?;
+                Ok(v2::ChangeEventProcessed {
+                    status: "success".into(),
+                    message: "Change event processed".into(),
+                })
+            }
+        }
+    }
+}
+
+/// The key field of `event` or, like PagerDuty does for events without
+/// one, a generated key.
+fn event_key(event: &Value, field: &str) -> String {
+    match event[field].as_str() {
+        Some(key) => key.into(),
+        None => Sha256::digest(event.to_string().as_bytes())[..16].iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect(),
+    }
+}
+
+fn json_line(endpoint: Endpoint, event: &Value) -> Result<Vec<u8>, ErrorResponse> {
+    let mut line = serde_json::to_vec(&json!({"endpoint": endpoint, "event": event}))?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+
+/// Appends events to a file, one JSON object per line holding the
+/// `endpoint` and the `event`.
+#[derive(Debug)]
+pub struct JsonLinesSink {
+    file: Mutex<File>,
+}
+
+impl JsonLinesSink {
+    /// Append to the file at `path`, creating it if needed.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<JsonLinesSink, ErrorResponse> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(JsonLinesSink { file: Mutex::new(file) })
+    }
+
+    fn record(&self, endpoint: Endpoint, event: &Value) -> Result<(), ErrorResponse> {
+        self.file.lock().unwrap().write_all(&json_line(endpoint, event)?)?;
+        Ok(())
+    }
+}
+
+local_sink!(JsonLinesSink);
+
+
+/// Prints events to stdout in the same format as `JsonLinesSink`.
+#[derive(Debug, Default)]
+pub struct StdoutSink;
+
+impl StdoutSink {
+    fn record(&self, endpoint: Endpoint, event: &Value) -> Result<(), ErrorResponse> {
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        stdout.write_all(&json_line(endpoint, event)?)?;
+        Ok(())
+    }
+}
+
+local_sink!(StdoutSink);
+
+
+/// Keeps events in memory so tests can assert on what would have been
+/// sent.
+#[derive(Debug, Default)]
+pub struct CaptureSink {
+    events: Mutex<Vec<(Endpoint, Value)>>,
+}
+
+impl CaptureSink {
+    pub fn new() -> CaptureSink {
+        CaptureSink::default()
+    }
+
+    /// Every event captured so far, oldest first.
+    pub fn events(&self) -> Vec<(Endpoint, Value)> {
+        self.events.lock().unwrap().clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forget the events captured so far.
+    pub fn clear(&self) {
+        self.events.lock().unwrap().clear();
+    }
+
+    /// Panics unless an event with `action` was captured for `key`, the
+    /// v1 `incident_key` or v2 `dedup_key` returned when it was sent.
+    /// `action` is the v1 `event_type` or v2 `event_action`, e.g.
+    /// `trigger`.
+    pub fn assert_sent(&self, action: &str, key: &str) {
+        let found = self.events.lock().unwrap().iter().any(|&(endpoint, ref event)| {
+            match endpoint {
+                Endpoint::V1 => event["event_type"] == action && event_key(event, "incident_key") == key,
+                Endpoint::V2 => event["event_action"] == action && event_key(event, "dedup_key") == key,
+                Endpoint::Change => false,
+            }
+        });
+        assert!(found, "no {} event captured for {}; captured: {:?}", action, key, self.events());
+    }
+
+    pub fn assert_triggered(&self, key: &str) {
+        self.assert_sent("trigger", key);
+    }
+
+    pub fn assert_acknowledged(&self, key: &str) {
+        self.assert_sent("acknowledge", key);
+    }
+
+    pub fn assert_resolved(&self, key: &str) {
+        self.assert_sent("resolve", key);
+    }
+
+    fn record(&self, endpoint: Endpoint, event: &Value) -> Result<(), ErrorResponse> {
+        self.events.lock().unwrap().push((endpoint, event.clone()));
+        Ok(())
+    }
+}
+
+local_sink!(CaptureSink);
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::io::Read;
+    use std::process;
+
+    use events::v1::{ResolveEvent, TriggerEvent};
+    use events::v2::{ChangeEvent, Severity};
+
+    #[test]
+    fn test_capture_sink() {
+        let sink = CaptureSink::new();
+
+        let processed = TriggerEvent::new("key".into(), "disk full".into())
+            .incident_key("db1/disk".into())
+            .send_with(&sink)
+            .unwrap();
+        assert_eq!(processed.incident_key, "db1/disk");
+
+        let processed = v2::TriggerEvent::new(
+            "key".into(), "disk full".into(), "db2".into(), Severity::Critical
+        ).send_with(&sink).unwrap();
+        assert_eq!(processed.dedup_key.len(), 32);
+
+        ResolveEvent::new("key".into(), "db1/disk".into()).send_with(&sink).unwrap();
+        ChangeEvent::new("key".into(), "deployed".into()).send_with(&sink).unwrap();
+
+        assert_eq!(sink.len(), 4);
+        sink.assert_triggered("db1/disk");
+        sink.assert_triggered(&processed.dedup_key);
+        sink.assert_resolved("db1/disk");
+        assert_eq!(sink.events()[3].0, Endpoint::Change);
+
+        sink.clear();
+        assert!(sink.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "no acknowledge event captured for db1/disk")]
+    fn test_capture_sink_assert_sent() {
+        let sink = CaptureSink::new();
+        TriggerEvent::new("key".into(), "disk full".into())
+            .incident_key("db1/disk".into())
+            .send_with(&sink)
+            .unwrap();
+        sink.assert_acknowledged("db1/disk");
+    }
+
+    #[test]
+    fn test_json_lines_sink() {
+        let path = env::temp_dir().join(format!("pagersduty-sink-{}.jsonl", process::id()));
+        let _ = fs::remove_file(&path);
+
+        let sink = JsonLinesSink::open(&path).unwrap();
+        ResolveEvent::new("key".into(), "db1/disk".into()).send_with(&sink).unwrap();
+
+        let mut data = String::new();
+        File::open(&path).unwrap().read_to_string(&mut data).unwrap();
+        assert_eq!(
+            data,
+            "{\"endpoint\":\"v1\",\"event\":{\"event_type\":\"resolve\",\
+             \"incident_key\":\"db1/disk\",\"service_key\":\"key\"}}\n"
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+}
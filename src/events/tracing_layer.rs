@@ -0,0 +1,315 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::{Map, Value};
+use tracing_core::{Event, Level, Subscriber};
+use tracing_core::field::{Field, Visit};
+use tracing_core::span::{Attributes, Id, Record};
+use tracing_subscriber::layer::{self, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+use events::pager::{Pager, is_sending, message_template};
+use events::sink::EventSink;
+use events::v1::{Context, IncidentKey, TriggerEvent};
+
+const DEFAULT_FIELD: &'static str = "page";
+
+const DEFAULT_RATE_LIMIT_SECS: u64 = 5 * 60;
+
+
+/// A `tracing_subscriber::Layer` that pages on events.
+///
+/// Events with the configured field set to `true`, e.g.
+/// `error!(page = true, "disk full")`, or at or above the configured level
+/// are sent as trigger events from a background thread. The fields of the
+/// event and its spans are included as details, and any that hold HTTP(S)
+/// URLs are attached as links named after the field. Events are grouped
+/// into incidents by target and message template, and each incident is
+/// paged at most once per rate limit window.
+pub struct PagingLayer {
+    service_key: String,
+    field: String,
+    level: Option<Level>,
+    pager: Pager,
+}
+
+impl PagingLayer {
+    pub fn builder(service_key: String) -> PagingLayerBuilder {
+        PagingLayerBuilder::new(service_key)
+    }
+}
+
+impl<S> Layer<S> for PagingLayer where S: Subscriber + for<'a> LookupSpan<'a> {
+    fn on_new_span(&self, attrs: &Attributes, id: &Id, ctx: layer::Context<S>) {
+        let mut fields = Fields::default();
+        attrs.record(&mut fields);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(fields);
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record, ctx: layer::Context<S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut extensions = span.extensions_mut();
+            if let Some(fields) = extensions.get_mut::<Fields>() {
+                values.record(fields);
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event, ctx: layer::Context<S>) {
+        if is_sending() {
+            return;
+        }
+
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+
+        let metadata = event.metadata();
+        let paged = fields.0.remove(&self.field) == Some(Value::Bool(true)) ||
+            self.level.map_or(false, |level| *metadata.level() <= level);
+        if !paged {
+            return;
+        }
+
+        let message = match fields.0.remove("message") {
+            Some(Value::String(message)) => message,
+            _ => metadata.name().into(),
+        };
+
+        let mut contexts = links(&fields.0);
+        let mut spans = Vec::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                let extensions = span.extensions();
+                let span_fields = extensions.get::<Fields>().map(|f| f.0.clone()).unwrap_or_default();
+                contexts.extend(links(&span_fields));
+                spans.push(json!({"name": span.name(), "fields": span_fields}));
+            }
+        }
+
+        let incident_key = IncidentKey::new(self.service_key.clone(), metadata.target().into())
+            .label("template".into(), message_template(&message))
+            .key();
+
+        let mut event = TriggerEvent::new(
+            self.service_key.clone(),
+            format!("[{}] {}: {}", metadata.level(), metadata.target(), message),
+        )
+            .incident_key(incident_key.clone())
+            .details(json!({
+                "level": metadata.level().to_string(),
+                "target": metadata.target(),
+                "message": message,
+                "fields": fields.0,
+                "spans": spans,
+                "module_path": metadata.module_path(),
+                "file": metadata.file(),
+                "line": metadata.line(),
+            }))
+            .truncate_description();
+        if !contexts.is_empty() {
+            event = event.contexts(contexts);
+        }
+
+        self.pager.page(&incident_key, event);
+    }
+}
+
+
+/// The fields of an event or span, stored in the span's extensions.
+#[derive(Debug, Default)]
+struct Fields(Map<String, Value>);
+
+impl Visit for Fields {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().into(), json!(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().into(), json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().into(), json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().into(), json!(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().into(), json!(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().into(), json!(format!("{:?}", value)));
+    }
+}
+
+/// Links to the fields holding HTTP(S) URLs.
+fn links(fields: &Map<String, Value>) -> Vec<Context> {
+    fields.iter()
+        .filter_map(|(name, value)| {
+            let href = value.as_str()?;
+            if !href.starts_with("http://") && !href.starts_with("https://") {
+                return None;
+            }
+            Context::link(href.into(), Some(name.clone())).ok()
+        })
+        .collect()
+}
+
+
+pub struct PagingLayerBuilder {
+    service_key: String,
+    field: String,
+    level: Option<Level>,
+    rate_limit: Duration,
+    sink: Option<Arc<dyn EventSink + Send + Sync>>,
+}
+
+impl PagingLayerBuilder {
+    /// A builder paging on events with `page = true`, at most once every 5
+    /// minutes per incident.
+    pub fn new(service_key: String) -> PagingLayerBuilder {
+        PagingLayerBuilder {
+            service_key: service_key,
+            field: DEFAULT_FIELD.into(),
+            level: None,
+            rate_limit: Duration::from_secs(DEFAULT_RATE_LIMIT_SECS),
+            sink: None,
+        }
+    }
+
+    /// Page on events with `field` set to `true`, instead of `page`.
+    pub fn field(mut self, field: String) -> PagingLayerBuilder {
+        self.field = field;
+        self
+    }
+
+    /// Also page on every event at or above `level`.
+    pub fn level(mut self, level: Level) -> PagingLayerBuilder {
+        self.level = Some(level);
+        self
+    }
+
+    /// The minimum time between pages for the same incident.
+    pub fn rate_limit(mut self, rate_limit: Duration) -> PagingLayerBuilder {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    /// Send pages through `sink` instead of the default used by
+    /// `TriggerEvent::send`.
+    pub fn sink(mut self, sink: Arc<dyn EventSink + Send + Sync>) -> PagingLayerBuilder {
+        self.sink = Some(sink);
+        self
+    }
+
+    /// Build the layer, starting the thread that sends pages.
+    pub fn build(self) -> PagingLayer {
+        PagingLayer {
+            service_key: self.service_key,
+            field: self.field,
+            level: self.level,
+            pager: Pager::new(self.sink, self.rate_limit),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::thread;
+    use std::time::Instant;
+    use tracing_core::{Dispatch, Metadata};
+    use tracing_core::callsite::{DefaultCallsite, Identifier};
+    use tracing_core::dispatcher;
+    use tracing_core::field::{FieldSet, Value as FieldValue};
+    use tracing_core::metadata::Kind;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::registry::Registry;
+
+    use events::sink::CaptureSink;
+
+    // What the `tracing` macros expand to, written out so the tests don't
+    // need the `tracing` crate and its much newer compiler.
+    macro_rules! callsite {
+        ($callsite:ident, $metadata:ident, $name:expr, $level:expr, $kind:expr, [$($field:expr),*]) => {
+            static $callsite: DefaultCallsite = DefaultCallsite::new(&$metadata);
+            static $metadata: Metadata<'static> = Metadata::new(
+                $name, "pagersduty::events::tracing_layer::tests", $level,
+                Some(file!()), Some(line!()), Some(module_path!()),
+                FieldSet::new(&[$($field),*], Identifier(&$callsite)), $kind,
+            );
+        }
+    }
+
+    macro_rules! values {
+        ($metadata:ident, $($name:expr => $value:expr),*) => {
+            [$((&$metadata.fields().field($name).unwrap(), Some(&$value as &dyn FieldValue))),*]
+        }
+    }
+
+    callsite!(SPAN_CALLSITE, SPAN, "request", Level::INFO, Kind::SPAN, ["id", "dashboard"]);
+    callsite!(INFO_CALLSITE, INFO, "info", Level::INFO, Kind::EVENT, ["message"]);
+    callsite!(WARN_CALLSITE, WARN, "warn", Level::WARN, Kind::EVENT, ["message", "page", "disk"]);
+    callsite!(ERROR_CALLSITE, ERROR, "error", Level::ERROR, Kind::EVENT, ["message"]);
+
+    #[test]
+    fn test_paging_layer() {
+        let sink = Arc::new(CaptureSink::new());
+        let layer = PagingLayer::builder("key".into())
+            .level(Level::ERROR)
+            .sink(sink.clone())
+            .build();
+        let dispatch = Dispatch::new(Registry::default().with(layer));
+
+        dispatcher::with_default(&dispatch, || {
+            let values = values!(SPAN, "id" => 42i64, "dashboard" => "https://grafana.example.com/d/api");
+            let span = dispatch.new_span(&Attributes::new(&SPAN, &SPAN.fields().value_set(&values)));
+            dispatch.enter(&span);
+
+            let values = values!(INFO, "message" => "request started");
+            Event::dispatch(&INFO, &INFO.fields().value_set(&values));
+            let values = values!(WARN, "message" => "disk 97% full", "page" => true, "disk" => "/var");
+            Event::dispatch(&WARN, &WARN.fields().value_set(&values));
+            let values = values!(ERROR, "message" => "request failed");
+            Event::dispatch(&ERROR, &ERROR.fields().value_set(&values));
+
+            dispatch.exit(&span);
+        });
+
+        let start = Instant::now();
+        while sink.len() < 2 && start.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let events = sink.events();
+        assert_eq!(events.len(), 2);
+
+        let event = &events[0].1;
+        assert_eq!(event["description"], "[WARN] pagersduty::events::tracing_layer::tests: disk 97% full");
+        assert_eq!(event["details"]["fields"], json!({"disk": "/var"}));
+        assert_eq!(
+            event["details"]["spans"],
+            json!([{
+                "name": "request",
+                "fields": {"id": 42, "dashboard": "https://grafana.example.com/d/api"},
+            }])
+        );
+        assert_eq!(
+            event["contexts"],
+            json!([{"type": "link", "href": "https://grafana.example.com/d/api", "text": "dashboard"}])
+        );
+
+        assert_eq!(
+            events[1].1["description"],
+            "[ERROR] pagersduty::events::tracing_layer::tests: request failed"
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde_json;
+
+use events::sink::EventSink;
+use events::v1::{ErrorResponse, EventProcessed, ResolveEvent, TriggerEvent};
+
+
+/// Numbers the temporary files state is written to, so that trackers
+/// saving to the same path don't write to the same temporary file.
+static SAVES: AtomicUsize = AtomicUsize::new(0);
+
+
+/// The state persisted between restarts.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+struct State {
+    /// Maps the key of each open incident to the service key it was
+    /// triggered on.
+    incidents: BTreeMap<String, String>,
+}
+
+
+/// Remembers which incidents have been triggered so they can be resolved
+/// once the problem goes away.
+///
+/// Trigger events are recorded under the incident key PagerDuty returns, so
+/// events without an `incident_key` are tracked too. When created with
+/// `open`, the open incidents are saved to a file after every change and
+/// loaded again on startup; `reconcile` then resolves those that recovered
+/// while the process was down. Saving replaces the whole file, so trackers
+/// in different processes shouldn't share one.
+#[derive(Debug, Default)]
+pub struct IncidentTracker {
+    path: Option<PathBuf>,
+    state: State,
+}
+
+impl IncidentTracker {
+    /// A tracker that only keeps its state in memory.
+    pub fn new() -> IncidentTracker {
+        IncidentTracker::default()
+    }
+
+    /// A tracker persisting its state to the file at `path`, loading any
+    /// state saved there by a previous process.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<IncidentTracker, ErrorResponse> {
+        let path = path.as_ref().to_path_buf();
+        let state = match File::open(&path) {
+            Ok(file) => serde_json::from_reader(file)?,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => State::default(),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(IncidentTracker {
+            path: Some(path),
+            state: state,
+        })
+    }
+
+    /// Whether an incident with `incident_key` has been triggered and not
+    /// yet resolved.
+    pub fn is_open(&self, incident_key: &str) -> bool {
+        self.state.incidents.contains_key(incident_key)
+    }
+
+    /// The keys of all open incidents.
+    pub fn open_incidents(&self) -> Vec<&str> {
+        self.state.incidents.keys().map(|key| key.as_str()).collect()
+    }
+
+    /// Validate and send a trigger event through `sink`, e.g. an
+    /// `EventsClient`, and remember the incident it opened.
+    pub fn trigger<S: EventSink + ?Sized>(
+        &mut self, sink: &S, event: TriggerEvent
+    ) -> Result<EventProcessed, ErrorResponse> {
+        let service_key = serde_json::to_value(&event)?["service_key"]
+            .as_str().unwrap_or_default().to_owned();
+
+        let processed = event.send_with(sink)?;
+        self.state.incidents.insert(processed.incident_key.clone(), service_key);
+        self.save()?;
+        Ok(processed)
+    }
+
+    /// Resolve the incident with `incident_key` if it is open, e.g. because
+    /// its check recovered. Does nothing for incidents that aren't open.
+    pub fn recover<S: EventSink + ?Sized>(
+        &mut self, sink: &S, incident_key: &str
+    ) -> Result<Option<EventProcessed>, ErrorResponse> {
+        let service_key = match self.state.incidents.get(incident_key) {
+            Some(service_key) => service_key.clone(),
+            None => return Ok(None),
+        };
+
+        let processed = ResolveEvent::new(service_key, incident_key.into()).send_with(sink)?;
+        self.state.incidents.remove(incident_key);
+        self.save()?;
+        Ok(Some(processed))
+    }
+
+    /// Resolve every open incident that isn't in `failing`, the keys of
+    /// the checks still failing. Meant to be called after a restart, once
+    /// the checks have run again.
+    pub fn reconcile<'a, S, I>(
+        &mut self, sink: &S, failing: I
+    ) -> Result<Vec<EventProcessed>, ErrorResponse>
+        where S: EventSink + ?Sized, I: IntoIterator<Item = &'a str>
+    {
+        let failing: BTreeSet<&str> = failing.into_iter().collect();
+        let recovered: Vec<String> = self.state.incidents.keys()
+            .filter(|key| !failing.contains(key.as_str()))
+            .cloned()
+            .collect();
+
+        let mut resolved = Vec::new();
+        for key in recovered {
+            if let Some(processed) = self.recover(sink, &key)? {
+                resolved.push(processed);
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// Forget an incident without resolving it, e.g. because it was
+    /// resolved in PagerDuty directly.
+    pub fn forget(&mut self, incident_key: &str) -> Result<(), ErrorResponse> {
+        if self.state.incidents.remove(incident_key).is_some() {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<(), ErrorResponse> {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return Ok(()),
+        };
+
+        let tmp_path = path.with_extension(
+            format!("{}.{}.tmp", process::id(), SAVES.fetch_add(1, Ordering::SeqCst))
+        );
+        {
+            let file = File::create(&tmp_path)?;
+            serde_json::to_writer(&file, &self.state)?;
+            file.sync_all()?;
+        }
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::env;
+    use std::process;
+
+    use events::sink::CaptureSink;
+
+    fn state_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("pagersduty-tracker-{}-{}.json", name, process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn trigger(key: &str) -> TriggerEvent {
+        TriggerEvent::new("service".into(), format!("{} failing", key)).incident_key(key.into())
+    }
+
+    #[test]
+    fn test_state_is_persisted() {
+        let path = state_path("state");
+
+        {
+            let mut tracker = IncidentTracker::open(&path).unwrap();
+            assert!(tracker.open_incidents().is_empty());
+
+            tracker.state.incidents.insert("db1/disk".into(), "service".into());
+            tracker.state.incidents.insert("db2/disk".into(), "service".into());
+            tracker.save().unwrap();
+            tracker.forget("db2/disk").unwrap();
+        }
+
+        let tracker = IncidentTracker::open(&path).unwrap();
+        assert_eq!(tracker.open_incidents(), vec!["db1/disk"]);
+        assert!(tracker.is_open("db1/disk"));
+        assert!(!tracker.is_open("db2/disk"));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_trigger_and_recover() {
+        let sink = CaptureSink::new();
+        let mut tracker = IncidentTracker::new();
+
+        tracker.trigger(&sink, trigger("db1/disk")).unwrap();
+        assert!(tracker.is_open("db1/disk"));
+        sink.assert_triggered("db1/disk");
+
+        // Events without an incident key are tracked under the one returned.
+        let processed = tracker.trigger(&sink, TriggerEvent::new("service".into(), "down".into())).unwrap();
+        assert!(tracker.is_open(&processed.incident_key));
+
+        assert!(tracker.recover(&sink, "db1/disk").unwrap().is_some());
+        assert!(!tracker.is_open("db1/disk"));
+        sink.assert_resolved("db1/disk");
+
+        sink.clear();
+        assert_eq!(tracker.recover(&sink, "db1/disk").unwrap(), None);
+        assert!(sink.is_empty());
+
+        // Invalid events are rejected without being tracked.
+        let invalid = TriggerEvent::new("service".into(), "x".repeat(2000)).incident_key("db2/disk".into());
+        match tracker.trigger(&sink, invalid) {
+            Err(ErrorResponse::Validation(_)) => {},
+            result => panic!("expected a validation error, got {:?}", result),
+        }
+        assert!(!tracker.is_open("db2/disk"));
+        assert!(sink.is_empty());
+    }
+
+    #[test]
+    fn test_reconcile_after_reopening() {
+        let path = state_path("reconcile");
+        let sink = CaptureSink::new();
+
+        {
+            let mut tracker = IncidentTracker::open(&path).unwrap();
+            tracker.trigger(&sink, trigger("db1/disk")).unwrap();
+            tracker.trigger(&sink, trigger("db2/disk")).unwrap();
+        }
+
+        let mut tracker = IncidentTracker::open(&path).unwrap();
+        assert_eq!(tracker.open_incidents(), vec!["db1/disk", "db2/disk"]);
+
+        sink.clear();
+        let resolved = tracker.reconcile(&sink, vec!["db2/disk"]).unwrap();
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(sink.len(), 1);
+        sink.assert_resolved("db1/disk");
+
+        let tracker = IncidentTracker::open(&path).unwrap();
+        assert_eq!(tracker.open_incidents(), vec!["db2/disk"]);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
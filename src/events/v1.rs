@@ -1,44 +1,32 @@
-use std::io::Read;
-use std::error::Error;
-
-use reqwest::{Client, StatusCode};
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use reqwest::{self, StatusCode};
+use serde::de::{Deserialize, Deserializer};
 use serde::ser::Serialize;
 use serde_json;
+use sha2::{Digest, Sha256};
 
-static EVENTS_URL: &'static str = "https://events.pagerduty.com/generic/2010-04-15/create_event.json";
+use errors;
+use events::client::default_client;
+use events::sink::{EventSink, default_sink};
+use events::v2;
 
 
+/// Send `event` through the default sink if one is set, otherwise using the
+/// shared default [`EventsClient`](../client/struct.EventsClient.html).
 pub fn send<T: Serialize>(event: T) -> Result<EventProcessed, ErrorResponse> {
-    let client = Client::new().map_err(ErrorResponse::unexpected)?;
-
-    let mut response = client
-        .post(EVENTS_URL).map_err(ErrorResponse::unexpected)?
-        .json(&event).map_err(ErrorResponse::unexpected)?
-        .send().map_err(ErrorResponse::unexpected)?;
-
-    let status = response.status();
-
-    if status == StatusCode::Forbidden {
-        return Err(ErrorResponse::RateLimited);
-    }
-
-    let mut output = String::new();
-    response.read_to_string(&mut output).map_err(ErrorResponse::unexpected)?;
-
-    match status {
-        StatusCode::Ok => {
-            Ok(serde_json::from_str(&output).map_err(ErrorResponse::unexpected)?)
-        },
-        StatusCode::BadRequest => {
-            Err(serde_json::from_str(&output).map_err(ErrorResponse::unexpected)?)
-        },
-        _ => Err(ErrorResponse::Unexpected(output)),
+    match default_sink() {
+        Some(sink) => sink.send_v1(&serde_json::to_value(event)?),
+        None => default_client()?.send_v1(event),
     }
 }
 
 
-#[derive(Deserialize, Debug, PartialEq)]
-#[serde(untagged)]
+#[derive(Debug)]
 pub enum ErrorResponse {
 
     /// If the event is improperly formatted this will be returned.
@@ -48,21 +36,313 @@ pub enum ErrorResponse {
         errors: Vec<String>,
     },
 
+    /// The event failed validation before it was sent, and would have been
+    /// rejected by the events endpoint.
+    Validation(Vec<Violation>),
+
     /// There is a limit on the number of events that a service can accept at
     /// any given time. If the service has received too many events this error
     /// will be returned. If it is vital that all events your monitoring tool
     /// sends be received, be sure to retry (preferably with a back off).
-    RateLimited,
+    RateLimited {
+        /// How long the endpoint asked to wait before retrying, if it said.
+        retry_after: Option<Duration>,
+    },
+
+    /// The events endpoint failed to process the event because of an
+    /// internal error. The event can safely be retried.
+    ServerError {
+        status: StatusCode,
+        body: String,
+    },
+
+    /// The event could not be sent, or the response could not be read or
+    /// decoded. The underlying error is kept as the source.
+    Transport(errors::Error),
 
     /// This return is used for all other errors that this library didn't
-    /// account for.
+    /// account for. Holds the body of the response.
     Unexpected(String),
 
 }
 
+impl fmt::Display for ErrorResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ErrorResponse::InvalidEvent { ref message, ref errors, .. } => {
+                write!(f, "invalid event: {}", message)?;
+                if !errors.is_empty() {
+                    write!(f, " ({})", errors.join("; "))?;
+                }
+                Ok(())
+            },
+            ErrorResponse::Validation(ref violations) => {
+                let violations: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+                write!(f, "invalid event: {}", violations.join("; "))
+            },
+            ErrorResponse::RateLimited { retry_after: Some(ref retry_after) } => {
+                write!(
+                    f, "rate limited by the events endpoint, retry after {}s",
+                    retry_after.as_secs()
+                )
+            },
+            ErrorResponse::RateLimited { retry_after: None } => {
+                write!(f, "rate limited by the events endpoint")
+            },
+            ErrorResponse::ServerError { ref status, ref body } => {
+                write!(f, "events endpoint responded with {}: {}", status, body)
+            },
+            ErrorResponse::Transport(ref err) => err.fmt(f),
+            ErrorResponse::Unexpected(ref body) => {
+                write!(f, "unexpected response from the events endpoint: {}", body)
+            },
+        }
+    }
+}
+
 impl ErrorResponse {
-    fn unexpected<T: Error>(err: T) -> ErrorResponse {
-        ErrorResponse::Unexpected(err.description().into())
+    /// Whether sending the same event again may succeed: the endpoint was
+    /// rate limiting, failed internally, or couldn't be reached, or the
+    /// connection failed while reading its response.
+    pub fn is_retryable(&self) -> bool {
+        match *self {
+            ErrorResponse::RateLimited { .. } => true,
+            ErrorResponse::ServerError { .. } => true,
+            ErrorResponse::Transport(errors::Error::Http(_)) => true,
+            ErrorResponse::Transport(errors::Error::Io(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl error::Error for ErrorResponse {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            ErrorResponse::Transport(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Transport errors are compared by kind, since the errors they wrap can't
+/// be compared.
+impl PartialEq for ErrorResponse {
+    fn eq(&self, other: &ErrorResponse) -> bool {
+        match (self, other) {
+            (
+                &ErrorResponse::InvalidEvent { status: ref a_status, message: ref a_message, errors: ref a_errors },
+                &ErrorResponse::InvalidEvent { status: ref b_status, message: ref b_message, errors: ref b_errors },
+            ) => a_status == b_status && a_message == b_message && a_errors == b_errors,
+            (&ErrorResponse::Validation(ref a), &ErrorResponse::Validation(ref b)) => a == b,
+            (
+                &ErrorResponse::RateLimited { retry_after: ref a },
+                &ErrorResponse::RateLimited { retry_after: ref b },
+            ) => a == b,
+            (
+                &ErrorResponse::ServerError { status: ref a_status, body: ref a_body },
+                &ErrorResponse::ServerError { status: ref b_status, body: ref b_body },
+            ) => a_status == b_status && a_body == b_body,
+            (&ErrorResponse::Transport(ref a), &ErrorResponse::Transport(ref b)) => same_kind(a, b),
+            (&ErrorResponse::Unexpected(ref a), &ErrorResponse::Unexpected(ref b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+fn same_kind(a: &errors::Error, b: &errors::Error) -> bool {
+    match (a, b) {
+        (&errors::Error::Http(_), &errors::Error::Http(_)) => true,
+        (&errors::Error::Io(ref a), &errors::Error::Io(ref b)) => a.kind() == b.kind(),
+        (&errors::Error::Json(ref a), &errors::Error::Json(ref b)) => a.classify() == b.classify(),
+        (
+            &errors::Error::Api { status: ref a_status, body: ref a_body },
+            &errors::Error::Api { status: ref b_status, body: ref b_body },
+        ) => a_status == b_status && a_body == b_body,
+        (&errors::Error::Events(ref a), &errors::Error::Events(ref b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Deserializes the error bodies the previous versions of this type could,
+/// i.e. an invalid event object, `null` for rate limiting, or any string.
+impl<'de> Deserialize<'de> for ErrorResponse {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ErrorResponse, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Body {
+            InvalidEvent {
+                status: String,
+                message: String,
+                errors: Vec<String>,
+            },
+            RateLimited,
+            Unexpected(String),
+        }
+
+        Ok(match Body::deserialize(deserializer)? {
+            Body::InvalidEvent { status, message, errors } => ErrorResponse::InvalidEvent {
+                status: status,
+                message: message,
+                errors: errors,
+            },
+            Body::RateLimited => ErrorResponse::RateLimited { retry_after: None },
+            Body::Unexpected(body) => ErrorResponse::Unexpected(body),
+        })
+    }
+}
+
+impl From<errors::Error> for ErrorResponse {
+    fn from(err: errors::Error) -> ErrorResponse {
+        ErrorResponse::Transport(err)
+    }
+}
+
+impl From<reqwest::Error> for ErrorResponse {
+    fn from(err: reqwest::Error) -> ErrorResponse {
+        ErrorResponse::Transport(err.into())
+    }
+}
+
+impl From<io::Error> for ErrorResponse {
+    fn from(err: io::Error) -> ErrorResponse {
+        ErrorResponse::Transport(err.into())
+    }
+}
+
+impl From<serde_json::Error> for ErrorResponse {
+    fn from(err: serde_json::Error) -> ErrorResponse {
+        ErrorResponse::Transport(err.into())
+    }
+}
+
+/// The body returned alongside an `InvalidEvent` error. Every field is
+/// optional so that a 400 response is never mistaken for another error.
+#[derive(Deserialize)]
+pub(crate) struct InvalidEventBody {
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub errors: Vec<String>,
+}
+
+impl InvalidEventBody {
+    /// Parse the body of a 400 response, falling back to using the raw body
+    /// as the message when it isn't the expected JSON object.
+    pub fn parse(body: &str) -> InvalidEventBody {
+        serde_json::from_str(body).unwrap_or_else(|_| InvalidEventBody {
+            status: "invalid event".into(),
+            message: body.into(),
+            errors: vec![],
+        })
+    }
+}
+
+impl From<InvalidEventBody> for ErrorResponse {
+    fn from(body: InvalidEventBody) -> ErrorResponse {
+        ErrorResponse::InvalidEvent {
+            status: body.status,
+            message: body.message,
+            errors: body.errors,
+        }
+    }
+}
+
+/// The longest description the events endpoint accepts, in characters.
+pub const MAX_DESCRIPTION_LENGTH: usize = 1024;
+
+/// The largest event the events endpoint accepts, in bytes.
+pub const MAX_EVENT_SIZE: usize = 512 * 1024;
+
+/// A reason the events endpoint would reject an event.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Violation {
+    /// The description is longer than `MAX_DESCRIPTION_LENGTH` characters.
+    DescriptionTooLong { length: usize },
+
+    /// An image context isn't served via HTTPS.
+    InsecureImageSource { src: String },
+
+    /// The serialized event is larger than `MAX_EVENT_SIZE` bytes.
+    EventTooLarge { size: usize },
+
+    /// An acknowledge or resolve event has an empty incident key.
+    MissingIncidentKey,
+
+    /// A link or image URL isn't a valid HTTP or HTTPS URL.
+    InvalidUrl { url: String },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Violation::DescriptionTooLong { length } => write!(
+                f, "description is {} characters, the limit is {}", length, MAX_DESCRIPTION_LENGTH
+            ),
+            Violation::InsecureImageSource { ref src } => {
+                write!(f, "image source {} isn't served via HTTPS", src)
+            },
+            Violation::EventTooLarge { size } => {
+                write!(f, "event is {} bytes, the limit is {}", size, MAX_EVENT_SIZE)
+            },
+            Violation::MissingIncidentKey => write!(f, "incident key is required"),
+            Violation::InvalidUrl { ref url } => write!(f, "{} isn't a valid HTTP(S) URL", url),
+        }
+    }
+}
+
+impl From<Violation> for ErrorResponse {
+    fn from(violation: Violation) -> ErrorResponse {
+        ErrorResponse::Validation(vec![violation])
+    }
+}
+
+/// Checks that `url` is an absolute HTTPS URL, or HTTP unless `https_only`.
+pub(crate) fn check_url(url: &str, https_only: bool) -> Result<(), Violation> {
+    match reqwest::Url::parse(url).as_ref().map(|url| url.scheme()) {
+        Ok("https") => Ok(()),
+        Ok("http") if !https_only => Ok(()),
+        Ok("http") => Err(Violation::InsecureImageSource { src: url.into() }),
+        _ => Err(Violation::InvalidUrl { url: url.into() }),
+    }
+}
+
+/// Checks a link to `href`.
+pub(crate) fn check_link(href: &str) -> Result<(), Violation> {
+    check_url(href, false)
+}
+
+/// Checks an image served from `src`, optionally linking to `href`.
+pub(crate) fn check_image(src: &str, href: Option<&str>) -> Result<(), Violation> {
+    check_url(src, true)?;
+    href.map_or(Ok(()), check_link)
+}
+
+/// Checks the length of a description, or of a v2 summary.
+pub(crate) fn check_description(description: &str) -> Result<(), Violation> {
+    let length = description.chars().count();
+    if length > MAX_DESCRIPTION_LENGTH {
+        return Err(Violation::DescriptionTooLong { length: length });
+    }
+    Ok(())
+}
+
+/// Checks the serialized size of `event`.
+pub(crate) fn validate_size<T: Serialize>(event: &T, violations: &mut Vec<Violation>) {
+    // Events that fail to serialize are reported when they're sent.
+    if let Ok(body) = serde_json::to_vec(event) {
+        if body.len() > MAX_EVENT_SIZE {
+            violations.push(Violation::EventTooLarge { size: body.len() });
+        }
+    }
+}
+
+pub(crate) fn into_result(violations: Vec<Violation>) -> Result<(), Vec<Violation>> {
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
     }
 }
 
@@ -73,7 +353,7 @@ pub struct EventProcessed {
     pub incident_key: String
 }
 
-#[derive(Serialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[serde(tag = "type")]
 pub enum Context {
 
@@ -104,6 +384,48 @@ pub enum Context {
     }
 }
 
+impl Context {
+    /// A link to `href`, which must be an HTTP or HTTPS URL.
+    pub fn link(href: String, text: Option<String>) -> Result<Context, Violation> {
+        check_link(&href)?;
+        Ok(Context::Link { href: href, text: text })
+    }
+
+    /// An image served from `src`, which must be an HTTPS URL, optionally
+    /// linking to the HTTP or HTTPS URL `href`.
+    pub fn image(src: String, href: Option<String>, alt: Option<String>) -> Result<Context, Violation> {
+        check_image(&src, href.as_ref().map(|href| href.as_str()))?;
+        Ok(Context::Image { src: src, href: href, alt: alt })
+    }
+
+    /// A link to the runbook for the alert.
+    pub fn runbook(href: String) -> Result<Context, Violation> {
+        Context::link(href, Some("Runbook".into()))
+    }
+
+    /// A link to a dashboard showing the affected system.
+    pub fn dashboard(href: String) -> Result<Context, Violation> {
+        Context::link(href, Some("Dashboard".into()))
+    }
+
+    /// A link to the logs of the affected system.
+    pub fn logs(href: String) -> Result<Context, Violation> {
+        Context::link(href, Some("Logs".into()))
+    }
+}
+
+impl From<v2::Link> for Context {
+    fn from(link: v2::Link) -> Context {
+        Context::Link { href: link.href, text: link.text }
+    }
+}
+
+impl From<v2::Image> for Context {
+    fn from(image: v2::Image) -> Context {
+        Context::Image { src: image.src, href: image.href, alt: image.alt }
+    }
+}
+
 
 #[derive(Serialize, Debug, PartialEq)]
 pub struct TriggerEvent {
@@ -178,9 +500,47 @@ impl TriggerEvent {
         self
     }
 
+    /// Shorten the description to `MAX_DESCRIPTION_LENGTH` characters,
+    /// ending it with an ellipsis, if it is longer.
+    pub fn truncate_description(mut self) -> TriggerEvent {
+        if self.description.chars().count() > MAX_DESCRIPTION_LENGTH {
+            self.description = self.description.chars()
+                .take(MAX_DESCRIPTION_LENGTH - 1)
+                .chain(Some('\u{2026}'))
+                .collect();
+        }
+        self
+    }
+
+    /// Check the event against the limits of the events endpoint, returning
+    /// every violation found.
+    pub fn validate(&self) -> Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+        violations.extend(check_description(&self.description).err());
+
+        for context in self.contexts.iter().flat_map(|contexts| contexts) {
+            let checked = match *context {
+                Context::Link { ref href, .. } => check_link(href),
+                Context::Image { ref src, ref href, .. } => check_image(src, href.as_ref().map(|href| href.as_str())),
+            };
+            violations.extend(checked.err());
+        }
+
+        validate_size(self, &mut violations);
+        into_result(violations)
+    }
+
+    /// Validate and send the event using the shared default client.
     pub fn send(self) -> Result<EventProcessed, ErrorResponse> {
+        self.validate().map_err(ErrorResponse::Validation)?;
         send(self)
     }
+
+    /// Validate and send the event through `sink`, e.g. an `EventsClient`.
+    pub fn send_with<S: EventSink + ?Sized>(self, sink: &S) -> Result<EventProcessed, ErrorResponse> {
+        self.validate().map_err(ErrorResponse::Validation)?;
+        sink.send_v1(&serde_json::to_value(self)?)
+    }
 }
 
 /// Acknowledge events cause the referenced incident to enter the acknowledged state.
@@ -213,9 +573,28 @@ impl AcknowledgeEvent {
         }
     }
 
+    /// Check the event against the limits of the events endpoint, returning
+    /// every violation found.
+    pub fn validate(&self) -> Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+        if self.incident_key.is_empty() {
+            violations.push(Violation::MissingIncidentKey);
+        }
+        validate_size(self, &mut violations);
+        into_result(violations)
+    }
+
+    /// Validate and send the event using the shared default client.
     pub fn send(self) -> Result<EventProcessed, ErrorResponse> {
+        self.validate().map_err(ErrorResponse::Validation)?;
         send(self)
     }
+
+    /// Validate and send the event through `sink`, e.g. an `EventsClient`.
+    pub fn send_with<S: EventSink + ?Sized>(self, sink: &S) -> Result<EventProcessed, ErrorResponse> {
+        self.validate().map_err(ErrorResponse::Validation)?;
+        sink.send_v1(&serde_json::to_value(self)?)
+    }
 }
 
 /// Resolve events cause the referenced incident to enter the resolved state.
@@ -249,15 +628,96 @@ impl ResolveEvent {
         }
     }
 
+    /// Check the event against the limits of the events endpoint, returning
+    /// every violation found.
+    pub fn validate(&self) -> Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+        if self.incident_key.is_empty() {
+            violations.push(Violation::MissingIncidentKey);
+        }
+        validate_size(self, &mut violations);
+        into_result(violations)
+    }
+
+    /// Validate and send the event using the shared default client.
     pub fn send(self) -> Result<EventProcessed, ErrorResponse> {
+        self.validate().map_err(ErrorResponse::Validation)?;
         send(self)
     }
+
+    /// Validate and send the event through `sink`, e.g. an `EventsClient`.
+    pub fn send_with<S: EventSink + ?Sized>(self, sink: &S) -> Result<EventProcessed, ErrorResponse> {
+        self.validate().map_err(ErrorResponse::Validation)?;
+        sink.send_v1(&serde_json::to_value(self)?)
+    }
+}
+
+/// Derives a stable `incident_key` from the identity of an alert, so that
+/// separate processes emitting the same logical alert converge on one
+/// incident without coordinating.
+///
+/// The key is the hex SHA-256 of a canonical encoding of the fields, so it
+/// doesn't depend on the order labels are added in, the process, or the
+/// version of Rust.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IncidentKey {
+    service: String,
+    check: String,
+    host: Option<String>,
+    labels: BTreeMap<String, String>,
+}
+
+impl IncidentKey {
+    pub fn new(service: String, check: String) -> IncidentKey {
+        IncidentKey {
+            service: service,
+            check: check,
+            host: None,
+            labels: BTreeMap::new(),
+        }
+    }
+
+    pub fn host(mut self, host: String) -> IncidentKey {
+        self.host = Some(host);
+        self
+    }
+
+    pub fn label(mut self, name: String, value: String) -> IncidentKey {
+        self.labels.insert(name, value);
+        self
+    }
+
+    /// The derived incident key.
+    pub fn key(&self) -> String {
+        let mut hasher = Sha256::new();
+
+        // Length-prefix every value so that no two different sets of fields
+        // share an encoding.
+        {
+            let mut field = |name: &str, value: &str| {
+                hasher.input(format!("{}:{}:{}\n", name, value.len(), value).as_bytes());
+            };
+            field("service", &self.service);
+            field("check", &self.check);
+            if let Some(ref host) = self.host {
+                field("host", host);
+            }
+            for (name, value) in &self.labels {
+                field("label", name);
+                field("value", value);
+            }
+        }
+
+        hasher.result().iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
 }
 
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use events::client::EventsClient;
     use serde_json;
     use std::fs::File;
     use std::io::Read;
@@ -354,4 +814,200 @@ mod tests {
 
         assert_eq!(json, expected);
     }
+
+    #[test]
+    fn test_error_response_error(){
+        use std::error::Error;
+
+        let err = ErrorResponse::InvalidEvent {
+            status: "invalid event".into(),
+            message: "Event object is invalid".into(),
+            errors: vec!["Service key is the wrong length (should be 32 characters)".into()],
+        };
+        assert_eq!(
+            err.to_string(),
+            "invalid event: Event object is invalid \
+             (Service key is the wrong length (should be 32 characters))"
+        );
+
+        let json_err = serde_json::from_str::<EventProcessed>("{").unwrap_err();
+        let err: ::errors::Error = ErrorResponse::from(json_err).into();
+        let source = err.source().unwrap();
+        assert!(source.is::<ErrorResponse>());
+        let source = source.source().unwrap();
+        assert!(source.is::<::errors::Error>());
+        assert!(source.source().unwrap().is::<serde_json::Error>());
+    }
+
+    #[test]
+    fn test_invalid_event_body_parse(){
+        let body = InvalidEventBody::parse(
+            r#"{"status": "invalid event", "message": "Event object is invalid"}"#
+        );
+        assert_eq!(body.message, "Event object is invalid");
+        assert!(body.errors.is_empty());
+
+        let body = InvalidEventBody::parse("Bad Request");
+        assert_eq!(body.status, "invalid event");
+        assert_eq!(body.message, "Bad Request");
+    }
+
+    #[test]
+    fn test_error_response_is_retryable(){
+        assert!(ErrorResponse::RateLimited { retry_after: None }.is_retryable());
+        assert!(ErrorResponse::ServerError {
+            status: StatusCode::ServiceUnavailable,
+            body: "".into(),
+        }.is_retryable());
+        assert!(ErrorResponse::from(io::Error::new(io::ErrorKind::ConnectionReset, "reset")).is_retryable());
+        assert!(!ErrorResponse::Unexpected("".into()).is_retryable());
+        assert!(!ErrorResponse::InvalidEvent {
+            status: "invalid event".into(),
+            message: "Event object is invalid".into(),
+            errors: vec![],
+        }.is_retryable());
+    }
+
+    #[test]
+    fn test_error_response_eq(){
+        let io_error = |kind| ErrorResponse::from(io::Error::new(kind, "read failed"));
+        assert_eq!(io_error(io::ErrorKind::TimedOut), io_error(io::ErrorKind::TimedOut));
+        assert_ne!(io_error(io::ErrorKind::TimedOut), io_error(io::ErrorKind::Other));
+        assert_ne!(io_error(io::ErrorKind::TimedOut), ErrorResponse::Unexpected("".into()));
+
+        let err: ErrorResponse = serde_json::from_str(
+            r#"{"status": "invalid event", "message": "Event object is invalid", "errors": []}"#
+        ).unwrap();
+        assert_eq!(err, ErrorResponse::InvalidEvent {
+            status: "invalid event".into(),
+            message: "Event object is invalid".into(),
+            errors: vec![],
+        });
+        let err: ErrorResponse = serde_json::from_str("null").unwrap();
+        assert_eq!(err, ErrorResponse::RateLimited { retry_after: None });
+    }
+
+    #[test]
+    fn test_incident_key() {
+        let key = IncidentKey::new("checkout".into(), "disk_usage".into())
+            .host("db1".into())
+            .label("mount".into(), "/var".into())
+            .label("env".into(), "prod".into());
+        let reordered = IncidentKey::new("checkout".into(), "disk_usage".into())
+            .label("env".into(), "prod".into())
+            .label("mount".into(), "/var".into())
+            .host("db1".into());
+
+        assert_eq!(key.key(), reordered.key());
+        assert_eq!(key.key(), "cb570e157a2c24eab1561ea46d13cf299b253eb718a77bd69b97fab287b6c672");
+        assert_eq!(key.key().len(), 64);
+
+        assert!(key.key() != key.clone().host("db2".into()).key());
+        assert!(
+            IncidentKey::new("a".into(), "bc".into()).key() !=
+            IncidentKey::new("ab".into(), "c".into()).key()
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let event = TriggerEvent::new("Some key".into(), "x".repeat(1100))
+            .contexts(vec![
+                Context::Image {
+                    src: "http://example.com/graph.png".into(),
+                    href: None,
+                    alt: None,
+                },
+                Context::Image {
+                    src: "https://example.com/graph.png".into(),
+                    href: None,
+                    alt: None,
+                },
+                Context::Link {
+                    href: "grafana/d/db1".into(),
+                    text: None,
+                },
+                Context::Link {
+                    href: "https://grafana.example.com/d/db1".into(),
+                    text: None,
+                },
+                Context::Image {
+                    src: "https://example.com/graph.png".into(),
+                    href: Some("ftp://example.com/graph".into()),
+                    alt: None,
+                },
+            ]);
+        assert_eq!(
+            event.validate(),
+            Err(vec![
+                Violation::DescriptionTooLong { length: 1100 },
+                Violation::InsecureImageSource { src: "http://example.com/graph.png".into() },
+                Violation::InvalidUrl { url: "grafana/d/db1".into() },
+                Violation::InvalidUrl { url: "ftp://example.com/graph".into() },
+            ])
+        );
+
+        let event = event.truncate_description();
+        assert_eq!(event.description.chars().count(), MAX_DESCRIPTION_LENGTH);
+        assert!(event.description.ends_with("x\u{2026}"));
+
+        let event = TriggerEvent::new("Some key".into(), "disk full".into())
+            .details(json!({"log": "x".repeat(MAX_EVENT_SIZE)}));
+        match event.validate() {
+            Err(ref violations) => match violations[..] {
+                [Violation::EventTooLarge { size }] => assert!(size > MAX_EVENT_SIZE),
+                _ => panic!("unexpected violations: {:?}", violations),
+            },
+            Ok(()) => panic!("expected violations"),
+        }
+
+        assert_eq!(
+            ResolveEvent::new("Some key".into(), "".into()).validate(),
+            Err(vec![Violation::MissingIncidentKey])
+        );
+        assert_eq!(AcknowledgeEvent::new("Some key".into(), "incident".into()).validate(), Ok(()));
+
+        let err = ResolveEvent::new("Some key".into(), "".into()).send_with(
+            &EventsClient::builder().url("http://127.0.0.1:9".into()).build().unwrap()
+        ).unwrap_err();
+        assert_eq!(err.to_string(), "invalid event: incident key is required");
+        assert!(!err.is_retryable());
+    }
+
+    #[test]
+    fn test_context_constructors() {
+        assert_eq!(
+            Context::runbook("https://wiki.example.com/runbooks/disk".into()),
+            Ok(Context::Link {
+                href: "https://wiki.example.com/runbooks/disk".into(),
+                text: Some("Runbook".into()),
+            })
+        );
+        assert_eq!(
+            Context::logs("logs.example.com/db1".into()),
+            Err(Violation::InvalidUrl { url: "logs.example.com/db1".into() })
+        );
+        assert_eq!(
+            Context::link("ftp://example.com/dump".into(), None),
+            Err(Violation::InvalidUrl { url: "ftp://example.com/dump".into() })
+        );
+        assert!(Context::dashboard("http://grafana.internal/d/db".into()).is_ok());
+
+        assert_eq!(
+            Context::image("http://example.com/graph.png".into(), None, None),
+            Err(Violation::InsecureImageSource { src: "http://example.com/graph.png".into() })
+        );
+        assert_eq!(
+            Context::image(
+                "https://example.com/graph.png".into(),
+                Some("http://grafana.internal/d/db".into()),
+                Some("Disk usage".into()),
+            ),
+            Ok(Context::Image {
+                src: "https://example.com/graph.png".into(),
+                href: Some("http://grafana.internal/d/db".into()),
+                alt: Some("Disk usage".into()),
+            })
+        );
+    }
 }
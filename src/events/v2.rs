@@ -0,0 +1,684 @@
+use serde::ser::Serialize;
+use serde_json;
+
+use events::client::default_client;
+use events::sink::{EventSink, default_sink};
+use events::v1::{self, Context, ErrorResponse, Violation};
+use events::v1::{check_description, check_image, check_link, into_result, validate_size};
+
+
+/// Send `event` through the default sink if one is set, otherwise using the
+/// shared default [`EventsClient`](../client/struct.EventsClient.html).
+pub fn send<T: Serialize>(event: T) -> Result<EventProcessed, ErrorResponse> {
+    match default_sink() {
+        Some(sink) => sink.send_v2(&serde_json::to_value(event)?),
+        None => default_client()?.send_v2(event),
+    }
+}
+
+/// Send the change `event` through the default sink if one is set,
+/// otherwise using the shared default
+/// [`EventsClient`](../client/struct.EventsClient.html).
+pub fn send_change<T: Serialize>(event: T) -> Result<ChangeEventProcessed, ErrorResponse> {
+    match default_sink() {
+        Some(sink) => sink.send_change(&serde_json::to_value(event)?),
+        None => default_client()?.send_change(event),
+    }
+}
+
+
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct EventProcessed {
+    pub status: String,
+    pub message: String,
+    pub dedup_key: String,
+}
+
+/// Change events are not deduplicated, so unlike `EventProcessed` no
+/// `dedup_key` is returned.
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct ChangeEventProcessed {
+    pub status: String,
+    pub message: String,
+}
+
+/// The perceived severity of the status the event is describing with
+/// respect to the affected system.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Critical,
+    Error,
+    Warning,
+    Info,
+}
+
+/// The type of event.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum EventAction {
+    Trigger,
+    Acknowledge,
+    Resolve,
+}
+
+/// A link to be attached to the incident.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Link {
+
+    /// The URL of the link to be attached.
+    pub href: String,
+
+    /// Plain text that describes the purpose of the link, and can be used as the link's text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+impl Link {
+    /// A link to `href`, which must be an HTTP or HTTPS URL.
+    pub fn new(href: String, text: Option<String>) -> Result<Link, Violation> {
+        check_link(&href)?;
+        Ok(Link { href: href, text: text })
+    }
+
+    /// A link to the runbook for the alert.
+    pub fn runbook(href: String) -> Result<Link, Violation> {
+        Link::new(href, Some("Runbook".into()))
+    }
+
+    /// A link to a dashboard showing the affected system.
+    pub fn dashboard(href: String) -> Result<Link, Violation> {
+        Link::new(href, Some("Dashboard".into()))
+    }
+
+    /// A link to the logs of the affected system.
+    pub fn logs(href: String) -> Result<Link, Violation> {
+        Link::new(href, Some("Logs".into()))
+    }
+}
+
+/// An image to be attached to the incident. Images must be served via HTTPS.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Image {
+
+    /// The source of the image being attached to the incident. This image must be served via HTTPS.
+    pub src: String,
+
+    /// Optional URL; makes the image a clickable link.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub href: Option<String>,
+
+    /// Optional alternative text for the image.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alt: Option<String>,
+}
+
+impl Image {
+    /// An image served from `src`, which must be an HTTPS URL, optionally
+    /// linking to the HTTP or HTTPS URL `href`.
+    pub fn new(src: String, href: Option<String>, alt: Option<String>) -> Result<Image, Violation> {
+        check_image(&src, href.as_ref().map(|href| href.as_str()))?;
+        Ok(Image { src: src, href: href, alt: alt })
+    }
+}
+
+/// Information about the alert being triggered.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Payload {
+
+    /// A brief text summary of the event, used to generate the summaries/titles of any associated alerts.
+    summary: String,
+
+    /// The unique location of the affected system, preferably a hostname or FQDN.
+    source: String,
+
+    /// The perceived severity of the status the event is describing with respect to the affected system.
+    severity: Severity,
+
+    /// The time at which the emitting tool detected or generated the event, in ISO 8601 format.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<String>,
+
+    /// Component of the source machine that is responsible for the event, for example `mysql` or `eth0`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    component: Option<String>,
+
+    /// Logical grouping of components of a service, for example `app-stack`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group: Option<String>,
+
+    /// The class/type of the event, for example `ping failure` or `cpu load`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    class: Option<String>,
+
+    /// Additional details about the event and affected system.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    custom_details: Option<serde_json::value::Value>,
+}
+
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct TriggerEvent {
+
+    /// The GUID of one of your Events API V2 integrations. This is the
+    /// "Integration Key" listed on the Events API V2 integration's detail page.
+    routing_key: String,
+
+    /// The type of event. Always `trigger` for trigger events.
+    event_action: EventAction,
+
+    /// Deduplication key for correlating triggers and resolves. If omitted,
+    /// PagerDuty generates one and returns it in the response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dedup_key: Option<String>,
+
+    /// Information about the alert being triggered.
+    payload: Payload,
+
+    /// The name of the monitoring client that is triggering this event.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client: Option<String>,
+
+    /// The URL of the monitoring client that is triggering this event.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_url: Option<String>,
+
+    /// List of links to include.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    links: Option<Vec<Link>>,
+
+    /// List of images to include.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    images: Option<Vec<Image>>,
+}
+
+impl TriggerEvent {
+    pub fn new(routing_key: String, summary: String, source: String, severity: Severity) -> TriggerEvent {
+        TriggerEvent {
+            routing_key: routing_key,
+            event_action: EventAction::Trigger,
+            dedup_key: None,
+            payload: Payload {
+                summary: summary,
+                source: source,
+                severity: severity,
+                timestamp: None,
+                component: None,
+                group: None,
+                class: None,
+                custom_details: None,
+            },
+            client: None,
+            client_url: None,
+            links: None,
+            images: None,
+        }
+    }
+
+    pub fn dedup_key(mut self, dedup_key: String) -> TriggerEvent {
+        self.dedup_key = Some(dedup_key);
+        self
+    }
+
+    pub fn timestamp(mut self, timestamp: String) -> TriggerEvent {
+        self.payload.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn component(mut self, component: String) -> TriggerEvent {
+        self.payload.component = Some(component);
+        self
+    }
+
+    pub fn group(mut self, group: String) -> TriggerEvent {
+        self.payload.group = Some(group);
+        self
+    }
+
+    pub fn class(mut self, class: String) -> TriggerEvent {
+        self.payload.class = Some(class);
+        self
+    }
+
+    pub fn custom_details(mut self, custom_details: serde_json::value::Value) -> TriggerEvent {
+        self.payload.custom_details = Some(custom_details);
+        self
+    }
+
+    pub fn client(mut self, client: String) -> TriggerEvent {
+        self.client = Some(client);
+        self
+    }
+
+    pub fn client_url(mut self, client_url: String) -> TriggerEvent {
+        self.client_url = Some(client_url);
+        self
+    }
+
+    pub fn links(mut self, links: Vec<Link>) -> TriggerEvent {
+        self.links = Some(links);
+        self
+    }
+
+    pub fn images(mut self, images: Vec<Image>) -> TriggerEvent {
+        self.images = Some(images);
+        self
+    }
+
+    /// Add v1 contexts, as links and images.
+    pub fn contexts(mut self, contexts: Vec<v1::Context>) -> TriggerEvent {
+        for context in contexts {
+            match context {
+                Context::Link { href, text } => {
+                    self.links.get_or_insert_with(Vec::new).push(Link { href: href, text: text });
+                },
+                Context::Image { src, href, alt } => {
+                    self.images.get_or_insert_with(Vec::new).push(Image { src: src, href: href, alt: alt });
+                },
+            }
+        }
+        self
+    }
+
+    /// Check the event against the limits of the events endpoint, the same
+    /// as for v1 events, returning every violation found.
+    pub fn validate(&self) -> Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+        violations.extend(check_description(&self.payload.summary).err());
+
+        for link in self.links.iter().flat_map(|links| links) {
+            violations.extend(check_link(&link.href).err());
+        }
+        for image in self.images.iter().flat_map(|images| images) {
+            violations.extend(check_image(&image.src, image.href.as_ref().map(|href| href.as_str())).err());
+        }
+
+        validate_size(self, &mut violations);
+        into_result(violations)
+    }
+
+    /// Validate and send the event using the shared default client.
+    pub fn send(self) -> Result<EventProcessed, ErrorResponse> {
+        self.validate().map_err(ErrorResponse::Validation)?;
+        send(self)
+    }
+
+    /// Validate and send the event through `sink`, e.g. an `EventsClient`.
+    pub fn send_with<S: EventSink + ?Sized>(self, sink: &S) -> Result<EventProcessed, ErrorResponse> {
+        self.validate().map_err(ErrorResponse::Validation)?;
+        sink.send_v2(&serde_json::to_value(self)?)
+    }
+}
+
+/// Acknowledge events cause the referenced incident to enter the acknowledged state.
+///
+/// While an incident is acknowledged, it won't generate any additional
+/// notifications, even if it receives new trigger events.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct AcknowledgeEvent {
+
+    /// The GUID of one of your Events API V2 integrations. This is the
+    /// "Integration Key" listed on the Events API V2 integration's detail page.
+    routing_key: String,
+
+    /// The type of event. Always `acknowledge` for acknowledge events.
+    event_action: EventAction,
+
+    /// Identifies the alert to acknowledge.
+    dedup_key: String,
+}
+
+impl AcknowledgeEvent {
+    pub fn new(routing_key: String, dedup_key: String) -> AcknowledgeEvent {
+        AcknowledgeEvent {
+            routing_key: routing_key,
+            event_action: EventAction::Acknowledge,
+            dedup_key: dedup_key,
+        }
+    }
+
+    pub fn send(self) -> Result<EventProcessed, ErrorResponse> {
+        send(self)
+    }
+
+    pub fn send_with<S: EventSink + ?Sized>(self, sink: &S) -> Result<EventProcessed, ErrorResponse> {
+        sink.send_v2(&serde_json::to_value(self)?)
+    }
+}
+
+/// Resolve events cause the referenced incident to enter the resolved state.
+///
+/// Once an incident is resolved, it won't generate any additional notifications.
+/// New trigger events with the same dedup_key as a resolved incident won't
+/// re-open the incident. Instead, a new incident will be created.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ResolveEvent {
+
+    /// The GUID of one of your Events API V2 integrations. This is the
+    /// "Integration Key" listed on the Events API V2 integration's detail page.
+    routing_key: String,
+
+    /// The type of event. Always `resolve` for resolve events.
+    event_action: EventAction,
+
+    /// Identifies the alert to resolve.
+    dedup_key: String,
+}
+
+impl ResolveEvent {
+    pub fn new(routing_key: String, dedup_key: String) -> ResolveEvent {
+        ResolveEvent {
+            routing_key: routing_key,
+            event_action: EventAction::Resolve,
+            dedup_key: dedup_key,
+        }
+    }
+
+    pub fn send(self) -> Result<EventProcessed, ErrorResponse> {
+        send(self)
+    }
+
+    pub fn send_with<S: EventSink + ?Sized>(self, sink: &S) -> Result<EventProcessed, ErrorResponse> {
+        sink.send_v2(&serde_json::to_value(self)?)
+    }
+}
+
+/// Information about the change being recorded.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ChangePayload {
+
+    /// A brief text summary of the event, displayed on the change timeline.
+    summary: String,
+
+    /// The time at which the change occurred, in ISO 8601 format.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<String>,
+
+    /// The unique name of the location where the change occurred.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+
+    /// Additional details about the change.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    custom_details: Option<serde_json::value::Value>,
+}
+
+/// Change events record changes to a system, such as deploys or
+/// configuration changes, in the service's change timeline. They never
+/// create incidents.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ChangeEvent {
+
+    /// The GUID of one of your Change Events API integrations. This is the
+    /// "Integration Key" listed on the integration's detail page.
+    routing_key: String,
+
+    /// Information about the change being recorded.
+    payload: ChangePayload,
+
+    /// List of links to include.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    links: Option<Vec<Link>>,
+}
+
+impl ChangeEvent {
+    pub fn new(routing_key: String, summary: String) -> ChangeEvent {
+        ChangeEvent {
+            routing_key: routing_key,
+            payload: ChangePayload {
+                summary: summary,
+                timestamp: None,
+                source: None,
+                custom_details: None,
+            },
+            links: None,
+        }
+    }
+
+    pub fn timestamp(mut self, timestamp: String) -> ChangeEvent {
+        self.payload.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn source(mut self, source: String) -> ChangeEvent {
+        self.payload.source = Some(source);
+        self
+    }
+
+    pub fn custom_details(mut self, custom_details: serde_json::value::Value) -> ChangeEvent {
+        self.payload.custom_details = Some(custom_details);
+        self
+    }
+
+    pub fn links(mut self, links: Vec<Link>) -> ChangeEvent {
+        self.links = Some(links);
+        self
+    }
+
+    pub fn send(self) -> Result<ChangeEventProcessed, ErrorResponse> {
+        send_change(self)
+    }
+
+    pub fn send_with<S: EventSink + ?Sized>(self, sink: &S) -> Result<ChangeEventProcessed, ErrorResponse> {
+        sink.send_change(&serde_json::to_value(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use serde_json;
+    use std::fs::File;
+    use std::io::Read;
+
+    use events::sink::CaptureSink;
+
+
+    #[test]
+    fn test_trigger_event_serialization_1(){
+        let event = TriggerEvent::new(
+            "Some key".into(), "some summary".into(), "db01".into(), Severity::Critical
+        );
+        let json: serde_json::Value = serde_json::from_str(
+            serde_json::to_string(&event).unwrap().as_ref()
+        ).unwrap();
+
+        let mut file = File::open("testdata/events/v2_trigger_event_serialization_1.json").unwrap();
+        let mut data = String::new();
+        file.read_to_string(&mut data).unwrap();
+        let expected: serde_json::Value = serde_json::from_str(&data).unwrap();
+
+        assert_eq!(json, expected);
+    }
+
+    #[test]
+    fn test_trigger_event_serialization_2(){
+
+        let custom_details = json!({
+            "free space": "1%",
+            "ping time": "1500ms",
+        });
+
+        let links = vec![
+            Link {
+                href: "https://example.com/".into(),
+                text: Some("Link text".into()),
+            },
+        ];
+
+        let images = vec![
+            Image {
+                src: "https://www.pagerduty.com/wp-content/uploads/2016/05/pagerduty-logo-green.png".into(),
+                href: Some("https://example.com/".into()),
+                alt: Some("Example text".into()),
+            },
+        ];
+
+        let event = TriggerEvent::new(
+            "Some key".into(), "some summary".into(), "db01".into(), Severity::Warning
+        )
+            .dedup_key("AG23DR1".into())
+            .timestamp("2015-07-17T08:42:58.315+0000".into())
+            .component("mysql".into())
+            .group("prod-datapipe".into())
+            .class("disk free".into())
+            .custom_details(custom_details)
+            .client("pagersduty-test".into())
+            .client_url("https://localhost".into())
+            .links(links)
+            .images(images);
+
+        let json: serde_json::Value = serde_json::from_str(
+            serde_json::to_string(&event).unwrap().as_ref()
+        ).unwrap();
+
+        let mut file = File::open("testdata/events/v2_trigger_event_serialization_2.json").unwrap();
+        let mut data = String::new();
+        file.read_to_string(&mut data).unwrap();
+        let expected: serde_json::Value = serde_json::from_str(&data).unwrap();
+
+        assert_eq!(json, expected);
+    }
+
+    #[test]
+    fn test_acknowledge_event_serialization(){
+        let event = AcknowledgeEvent::new(
+            "Some key".into(), "ASF123S".into()
+        );
+        let json: serde_json::Value = serde_json::from_str(
+            serde_json::to_string(&event).unwrap().as_ref()
+        ).unwrap();
+
+        let mut file = File::open("testdata/events/v2_handle_event_serialization_1.json").unwrap();
+        let mut data = String::new();
+        file.read_to_string(&mut data).unwrap();
+        let expected: serde_json::Value = serde_json::from_str(&data).unwrap();
+
+        assert_eq!(json, expected);
+    }
+
+    #[test]
+    fn test_resolve_event_serialization(){
+        let event = ResolveEvent::new(
+            "Some key".into(), "ASF123S".into()
+        );
+        let json: serde_json::Value = serde_json::from_str(
+            serde_json::to_string(&event).unwrap().as_ref()
+        ).unwrap();
+
+        let mut file = File::open("testdata/events/v2_handle_event_serialization_2.json").unwrap();
+        let mut data = String::new();
+        file.read_to_string(&mut data).unwrap();
+        let expected: serde_json::Value = serde_json::from_str(&data).unwrap();
+
+        assert_eq!(json, expected);
+    }
+
+    #[test]
+    fn test_change_event_serialization_1(){
+        let event = ChangeEvent::new("Some key".into(), "some summary".into());
+        let json: serde_json::Value = serde_json::from_str(
+            serde_json::to_string(&event).unwrap().as_ref()
+        ).unwrap();
+
+        let mut file = File::open("testdata/events/v2_change_event_serialization_1.json").unwrap();
+        let mut data = String::new();
+        file.read_to_string(&mut data).unwrap();
+        let expected: serde_json::Value = serde_json::from_str(&data).unwrap();
+
+        assert_eq!(json, expected);
+    }
+
+    #[test]
+    fn test_change_event_serialization_2(){
+
+        let custom_details = json!({
+            "build_state": "passed",
+            "build_number": "2",
+            "run_time": "1236s",
+        });
+
+        let links = vec![
+            Link {
+                href: "https://ci.example.com/builds/2".into(),
+                text: Some("View in CI".into()),
+            },
+        ];
+
+        let event = ChangeEvent::new("Some key".into(), "Build Success: Deploy api v1.2.3".into())
+            .timestamp("2020-07-17T08:42:58.315+0000".into())
+            .source("ci.example.com".into())
+            .custom_details(custom_details)
+            .links(links);
+
+        let json: serde_json::Value = serde_json::from_str(
+            serde_json::to_string(&event).unwrap().as_ref()
+        ).unwrap();
+
+        let mut file = File::open("testdata/events/v2_change_event_serialization_2.json").unwrap();
+        let mut data = String::new();
+        file.read_to_string(&mut data).unwrap();
+        let expected: serde_json::Value = serde_json::from_str(&data).unwrap();
+
+        assert_eq!(json, expected);
+    }
+
+    #[test]
+    fn test_contexts() {
+        let event = TriggerEvent::new(
+            "Some key".into(), "disk full".into(), "db1".into(), Severity::Critical
+        )
+            .links(vec![Link::runbook("https://wiki.example.com/runbooks/disk".into()).unwrap()])
+            .contexts(vec![
+                Context::dashboard("https://grafana.example.com/d/db".into()).unwrap(),
+                Context::image("https://example.com/graph.png".into(), None, None).unwrap(),
+            ]);
+
+        assert_eq!(
+            serde_json::to_value(&event).unwrap()["links"],
+            json!([
+                {"href": "https://wiki.example.com/runbooks/disk", "text": "Runbook"},
+                {"href": "https://grafana.example.com/d/db", "text": "Dashboard"},
+            ])
+        );
+        assert_eq!(
+            serde_json::to_value(&event).unwrap()["images"],
+            json!([{"src": "https://example.com/graph.png"}])
+        );
+        assert_eq!(
+            Image::new("http://example.com/graph.png".into(), None, None),
+            Err(Violation::InsecureImageSource { src: "http://example.com/graph.png".into() })
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let event = TriggerEvent::new(
+            "Some key".into(), "x".repeat(1100), "db1".into(), Severity::Critical
+        )
+            .links(vec![Link { href: "grafana/d/db1".into(), text: None }])
+            .images(vec![Image { src: "http://example.com/graph.png".into(), href: None, alt: None }]);
+        assert_eq!(
+            event.validate(),
+            Err(vec![
+                Violation::DescriptionTooLong { length: 1100 },
+                Violation::InvalidUrl { url: "grafana/d/db1".into() },
+                Violation::InsecureImageSource { src: "http://example.com/graph.png".into() },
+            ])
+        );
+
+        let sink = CaptureSink::new();
+        match event.send_with(&sink) {
+            Err(ErrorResponse::Validation(ref violations)) => assert_eq!(violations.len(), 3),
+            other => panic!("expected violations, got {:?}", other),
+        }
+        assert!(sink.is_empty());
+
+        let event = TriggerEvent::new("Some key".into(), "disk full".into(), "db1".into(), Severity::Critical)
+            .links(vec![Link::runbook("https://wiki.example.com/runbooks/disk".into()).unwrap()]);
+        assert_eq!(event.validate(), Ok(()));
+        assert!(event.send_with(&sink).is_ok());
+        assert_eq!(sink.len(), 1);
+    }
+}
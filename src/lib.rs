@@ -2,17 +2,20 @@
 
 // #![warn(missing_docs)]
 
+#[cfg(feature = "panic-hook")]
+extern crate backtrace;
+#[macro_use] extern crate lazy_static;
+#[cfg(feature = "log")]
+extern crate log;
 extern crate reqwest;
 extern crate serde;
 #[macro_use] extern crate serde_derive;
-
-// We only use the macros in tests currently.
-// This shuts up the warnings.
-#[cfg(not(test))]
-extern crate serde_json;
-#[cfg(test)]
-#[macro_use]
-extern crate serde_json;
+#[macro_use] extern crate serde_json;
+extern crate sha2;
+#[cfg(feature = "tracing")]
+extern crate tracing_core;
+#[cfg(feature = "tracing")]
+extern crate tracing_subscriber;
 
 pub mod errors;
 pub mod events;
@@ -1,11 +1,38 @@
-use reqwest::{self, Response};
+use std::collections::{BTreeSet, VecDeque};
+use std::io::Read;
+use std::vec;
+
+use reqwest::{self, RequestBuilder, Response, Url};
 use reqwest::header::{Headers, Accept, Authorization, qitem};
 use reqwest::mime::{Mime};
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json;
+
+use errors::{Error, Result};
+use rest::v2::dependency_graph::DependencyGraph;
+use rest::v2::types::alerts::{Alert, Alerts, AlertUpdate};
+use rest::v2::types::analytics::{
+    IncidentMetricsRequest, IncidentMetricsResponse, RawIncidentsRequest, RawIncidentsResponse
+};
+use rest::v2::types::audit_records::{AuditRecord, AuditRecords, AuditRecordsQuery};
+use rest::v2::types::business_services::{BusinessService, BusinessServices};
+use rest::v2::types::service_dependencies::{
+    DependencyService, ServiceDependency, ServiceDependencies
+};
+use rest::v2::types::webhook_subscriptions::{
+    WebhookSubscription, WebhookSubscriptions, WebhookSubscriptionUpdate
+};
 
 static PD_API_URL: &'static str = "https://api.pagerduty.com";
 
 static PD_API_MIME_TYPE: &'static str = "application/vnd.pagerduty+json;version=2";
 
+/// The page size used when walking paginated list endpoints.
+const PAGE_LIMIT: usize = 100;
+
+const NO_QUERY: [(&'static str, &'static str); 0] = [];
+
 
 #[derive(Debug)]
 pub struct Client {
@@ -47,6 +74,484 @@ impl Client {
             .send()
             .unwrap()
     }
+
+    /// List all alerts belonging to the incident identified by `incident_id`.
+    pub fn incident_alerts(&self, incident_id: &str) -> Result<Alerts> {
+        let path = format!("incidents/{}/alerts", incident_id);
+        self.get_all_pages(&path, |page: AlertsPage| (page.alerts, page.more))
+    }
+
+    /// Show detailed information about a single alert of an incident.
+    pub fn incident_alert(&self, incident_id: &str, alert_id: &str) -> Result<Alert> {
+        let path = format!("incidents/{}/alerts/{}", incident_id, alert_id);
+        let wrapper: AlertWrapper = self.get_json(&path, &NO_QUERY)?;
+        Ok(wrapper.alert)
+    }
+
+    /// Resolve a single alert of an incident. `from` is the email address
+    /// of a valid user associated with the account making the request.
+    pub fn resolve_incident_alert(
+        &self, from: &str, incident_id: &str, alert_id: &str
+    ) -> Result<Alert> {
+        self.update_incident_alert(from, incident_id, alert_id, AlertUpdate::resolve())
+    }
+
+    /// Move a single alert of an incident to the incident identified by
+    /// `new_incident_id`. `from` is the email address of a valid user
+    /// associated with the account making the request.
+    pub fn move_incident_alert(
+        &self, from: &str, incident_id: &str, alert_id: &str, new_incident_id: &str
+    ) -> Result<Alert> {
+        self.update_incident_alert(
+            from, incident_id, alert_id, AlertUpdate::move_to(new_incident_id)
+        )
+    }
+
+    fn update_incident_alert(
+        &self, from: &str, incident_id: &str, alert_id: &str, update: AlertUpdate
+    ) -> Result<Alert> {
+        let path = format!("incidents/{}/alerts/{}", incident_id, alert_id);
+        let mut headers = self.get_headers();
+        headers.set_raw("From", from.to_string());
+
+        let wrapper: AlertWrapper = self.put_json(&path, headers, &AlertUpdateWrapper {
+            alert: update,
+        })?;
+        Ok(wrapper.alert)
+    }
+
+    /// List audit records for the whole account, most recent first.
+    pub fn audit_records(&self, query: &AuditRecordsQuery) -> AuditRecordsIter {
+        AuditRecordsIter::new(self, "audit/records".into(), query)
+    }
+
+    /// List audit records for the user identified by `user_id`.
+    pub fn user_audit_records(
+        &self, user_id: &str, query: &AuditRecordsQuery
+    ) -> AuditRecordsIter {
+        AuditRecordsIter::new(self, format!("users/{}/audit/records", user_id), query)
+    }
+
+    /// List audit records for the team identified by `team_id`.
+    pub fn team_audit_records(
+        &self, team_id: &str, query: &AuditRecordsQuery
+    ) -> AuditRecordsIter {
+        AuditRecordsIter::new(self, format!("teams/{}/audit/records", team_id), query)
+    }
+
+    /// List audit records for the schedule identified by `schedule_id`.
+    pub fn schedule_audit_records(
+        &self, schedule_id: &str, query: &AuditRecordsQuery
+    ) -> AuditRecordsIter {
+        AuditRecordsIter::new(
+            self, format!("schedules/{}/audit/records", schedule_id), query
+        )
+    }
+
+    /// List audit records for the escalation policy identified by
+    /// `escalation_policy_id`.
+    pub fn escalation_policy_audit_records(
+        &self, escalation_policy_id: &str, query: &AuditRecordsQuery
+    ) -> AuditRecordsIter {
+        AuditRecordsIter::new(
+            self,
+            format!("escalation_policies/{}/audit/records", escalation_policy_id),
+            query,
+        )
+    }
+
+    /// Aggregated incident metrics across the whole account.
+    pub fn incident_metrics(
+        &self, request: &IncidentMetricsRequest
+    ) -> Result<IncidentMetricsResponse> {
+        self.post_json("analytics/metrics/incidents/all", self.get_analytics_headers(), request)
+    }
+
+    /// Aggregated incident metrics broken down by service.
+    pub fn service_incident_metrics(
+        &self, request: &IncidentMetricsRequest
+    ) -> Result<IncidentMetricsResponse> {
+        self.post_json(
+            "analytics/metrics/incidents/services", self.get_analytics_headers(), request
+        )
+    }
+
+    /// Aggregated incident metrics broken down by team.
+    pub fn team_incident_metrics(
+        &self, request: &IncidentMetricsRequest
+    ) -> Result<IncidentMetricsResponse> {
+        self.post_json(
+            "analytics/metrics/incidents/teams", self.get_analytics_headers(), request
+        )
+    }
+
+    /// Metrics for individual incidents. Results are paginated; pass
+    /// `RawIncidentsResponse::last` to `RawIncidentsRequest::starting_after`
+    /// while `more` is set to fetch subsequent pages.
+    pub fn raw_incidents(&self, request: &RawIncidentsRequest) -> Result<RawIncidentsResponse> {
+        self.post_json("analytics/raw/incidents", self.get_analytics_headers(), request)
+    }
+
+    // The analytics endpoints are only available behind an early access
+    // header.
+    fn get_analytics_headers(&self) -> Headers {
+        let mut headers = self.get_headers();
+        headers.set_raw("X-EARLY-ACCESS", "analytics-v2");
+        headers
+    }
+
+    /// List all business services.
+    pub fn business_services(&self) -> Result<BusinessServices> {
+        self.get_all_pages("business_services", |page: BusinessServicesPage| {
+            (page.business_services, page.more)
+        })
+    }
+
+    /// Show detailed information about a single business service.
+    pub fn business_service(&self, business_service_id: &str) -> Result<BusinessService> {
+        let path = format!("business_services/{}", business_service_id);
+        let wrapper: BusinessServiceWrapper = self.get_json(&path, &NO_QUERY)?;
+        Ok(wrapper.business_service)
+    }
+
+    /// List the immediate dependencies of a business or technical service.
+    pub fn service_dependencies(
+        &self, service: &DependencyService
+    ) -> Result<ServiceDependencies> {
+        let path = match *service {
+            DependencyService::Business { ref id } => {
+                format!("service_dependencies/business_services/{}", id)
+            },
+            DependencyService::Technical { ref id } => {
+                format!("service_dependencies/technical_services/{}", id)
+            },
+        };
+        let wrapper: RelationshipsWrapper = self.get_json(&path, &NO_QUERY)?;
+        Ok(wrapper.relationships)
+    }
+
+    /// Create the given dependencies between services.
+    pub fn associate_service_dependencies(
+        &self, relationships: &[ServiceDependency]
+    ) -> Result<ServiceDependencies> {
+        let body = RelationshipsBody { relationships: relationships };
+        let wrapper: RelationshipsWrapper = self.post_json(
+            "service_dependencies/associate", self.get_headers(), &body
+        )?;
+        Ok(wrapper.relationships)
+    }
+
+    /// Remove the given dependencies between services.
+    pub fn disassociate_service_dependencies(
+        &self, relationships: &[ServiceDependency]
+    ) -> Result<ServiceDependencies> {
+        let body = RelationshipsBody { relationships: relationships };
+        let wrapper: RelationshipsWrapper = self.post_json(
+            "service_dependencies/disassociate", self.get_headers(), &body
+        )?;
+        Ok(wrapper.relationships)
+    }
+
+    /// Load the dependency graph reachable from every business service.
+    ///
+    /// Technical services that are not connected, directly or
+    /// transitively, to a business service are not included; use
+    /// `service_dependency_graph_from` to start from them explicitly.
+    pub fn service_dependency_graph(&self) -> Result<DependencyGraph> {
+        let roots: Vec<DependencyService> = self.business_services()?
+            .into_iter()
+            .map(|business_service| {
+                let id = match business_service {
+                    BusinessService::Reference { id, .. } => id,
+                    BusinessService::BusinessService { id, .. } => id,
+                };
+                DependencyService::Business { id: id }
+            })
+            .collect();
+        self.service_dependency_graph_from(roots)
+    }
+
+    /// Load the dependency graph reachable from `roots`, following
+    /// relationships in both directions. Issues one request per service.
+    pub fn service_dependency_graph_from<I>(&self, roots: I) -> Result<DependencyGraph>
+        where I: IntoIterator<Item=DependencyService>
+    {
+        let mut graph = DependencyGraph::new();
+        let mut seen = BTreeSet::new();
+        let mut queue: VecDeque<DependencyService> = roots.into_iter().collect();
+
+        while let Some(service) = queue.pop_front() {
+            if seen.contains(&service) {
+                continue;
+            }
+
+            for dependency in self.service_dependencies(&service)? {
+                queue.push_back(dependency.supporting_service.clone());
+                queue.push_back(dependency.dependent_service.clone());
+                graph.add_dependency(&dependency);
+            }
+
+            graph.add_service(service.clone());
+            seen.insert(service);
+        }
+
+        Ok(graph)
+    }
+
+    /// List all webhook subscriptions.
+    pub fn webhook_subscriptions(&self) -> Result<WebhookSubscriptions> {
+        self.get_all_pages("webhook_subscriptions", |page: WebhookSubscriptionsPage| {
+            (page.webhook_subscriptions, page.more)
+        })
+    }
+
+    /// Show detailed information about a single webhook subscription.
+    pub fn webhook_subscription(&self, subscription_id: &str) -> Result<WebhookSubscription> {
+        let path = format!("webhook_subscriptions/{}", subscription_id);
+        let wrapper: WebhookSubscriptionWrapper = self.get_json(&path, &NO_QUERY)?;
+        Ok(wrapper.webhook_subscription)
+    }
+
+    /// Create a new webhook subscription. The returned subscription is the
+    /// only place the delivery method's signing `secret` is available.
+    pub fn create_webhook_subscription(
+        &self, subscription: &WebhookSubscription
+    ) -> Result<WebhookSubscription> {
+        let body = WebhookSubscriptionBody { webhook_subscription: subscription };
+        let wrapper: WebhookSubscriptionWrapper = self.post_json(
+            "webhook_subscriptions", self.get_headers(), &body
+        )?;
+        Ok(wrapper.webhook_subscription)
+    }
+
+    /// Update an existing webhook subscription.
+    pub fn update_webhook_subscription(
+        &self, subscription_id: &str, update: &WebhookSubscriptionUpdate
+    ) -> Result<WebhookSubscription> {
+        let path = format!("webhook_subscriptions/{}", subscription_id);
+        let body = WebhookSubscriptionBody { webhook_subscription: update };
+        let wrapper: WebhookSubscriptionWrapper = self.put_json(
+            &path, self.get_headers(), &body
+        )?;
+        Ok(wrapper.webhook_subscription)
+    }
+
+    /// Delete a webhook subscription.
+    pub fn delete_webhook_subscription(&self, subscription_id: &str) -> Result<()> {
+        let path = format!("webhook_subscriptions/{}", subscription_id);
+        let request = self.http_client
+            .delete(self.get_url(&path, &NO_QUERY))?
+            .headers(self.get_headers());
+        self.send(request).map(|_| ())
+    }
+
+    /// Send a `pagey.ping` test event to the subscription's receiver.
+    pub fn ping_webhook_subscription(&self, subscription_id: &str) -> Result<()> {
+        let path = format!("webhook_subscriptions/{}/ping", subscription_id);
+        let request = self.http_client
+            .post(self.get_url(&path, &NO_QUERY))?
+            .headers(self.get_headers());
+        self.send(request).map(|_| ())
+    }
+
+    fn get_all_pages<P, T, F>(&self, path: &str, into_items: F) -> Result<Vec<T>>
+        where P: DeserializeOwned, F: Fn(P) -> (Vec<T>, bool)
+    {
+        let mut items = Vec::new();
+        loop {
+            let query = [
+                ("limit", PAGE_LIMIT.to_string()),
+                ("offset", items.len().to_string()),
+            ];
+            let (page, more) = into_items(self.get_json(path, &query)?);
+            let more = more && !page.is_empty();
+            items.extend(page);
+            if !more {
+                return Ok(items);
+            }
+        }
+    }
+
+    fn get_url<K, V>(&self, path: &str, query: &[(K, V)]) -> Url
+        where K: AsRef<str>, V: AsRef<str>
+    {
+        let mut url: Url = format!("{}/{}", PD_API_URL, path).parse().unwrap();
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+        url
+    }
+
+    fn get_json<K, V, T>(&self, path: &str, query: &[(K, V)]) -> Result<T>
+        where K: AsRef<str>, V: AsRef<str>, T: DeserializeOwned
+    {
+        let request = self.http_client
+            .get(self.get_url(path, query))?
+            .headers(self.get_headers());
+        self.send_json(request)
+    }
+
+    fn put_json<B: Serialize, T: DeserializeOwned>(
+        &self, path: &str, headers: Headers, body: &B
+    ) -> Result<T> {
+        let request = self.http_client
+            .put(self.get_url(path, &NO_QUERY))?
+            .headers(headers)
+            .json(body)?;
+        self.send_json(request)
+    }
+
+    fn post_json<B: Serialize, T: DeserializeOwned>(
+        &self, path: &str, headers: Headers, body: &B
+    ) -> Result<T> {
+        let request = self.http_client
+            .post(self.get_url(path, &NO_QUERY))?
+            .headers(headers)
+            .json(body)?;
+        self.send_json(request)
+    }
+
+    fn send_json<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        let output = self.send(request)?;
+        Ok(serde_json::from_str(&output)?)
+    }
+
+    /// Send `request`, returning the response body if the API responded
+    /// with a successful status code.
+    fn send(&self, request: RequestBuilder) -> Result<String> {
+        let mut response = request.send()?;
+        let status = response.status();
+
+        let mut output = String::new();
+        response.read_to_string(&mut output)?;
+
+        if !status.is_success() {
+            return Err(Error::Api {
+                status: status,
+                body: output,
+            });
+        }
+
+        Ok(output)
+    }
+}
+
+
+#[derive(Deserialize)]
+struct AlertsPage {
+    alerts: Alerts,
+    more: bool,
+}
+
+#[derive(Deserialize)]
+struct AlertWrapper {
+    alert: Alert,
+}
+
+#[derive(Serialize)]
+struct AlertUpdateWrapper {
+    alert: AlertUpdate,
+}
+
+#[derive(Deserialize)]
+struct BusinessServicesPage {
+    business_services: BusinessServices,
+    more: bool,
+}
+
+#[derive(Deserialize)]
+struct BusinessServiceWrapper {
+    business_service: BusinessService,
+}
+
+#[derive(Deserialize)]
+struct RelationshipsWrapper {
+    relationships: ServiceDependencies,
+}
+
+#[derive(Serialize)]
+struct RelationshipsBody<'a> {
+    relationships: &'a [ServiceDependency],
+}
+
+#[derive(Deserialize)]
+struct WebhookSubscriptionsPage {
+    webhook_subscriptions: WebhookSubscriptions,
+    more: bool,
+}
+
+#[derive(Deserialize)]
+struct WebhookSubscriptionWrapper {
+    webhook_subscription: WebhookSubscription,
+}
+
+#[derive(Serialize)]
+struct WebhookSubscriptionBody<'a, T: 'a> {
+    webhook_subscription: &'a T,
+}
+
+#[derive(Deserialize)]
+struct AuditRecordsPage {
+    records: AuditRecords,
+    next_cursor: Option<String>,
+}
+
+
+/// Iterates over cursor-paginated audit records, fetching pages lazily as
+/// the iterator is advanced. Iteration stops after the first error.
+pub struct AuditRecordsIter<'a> {
+    client: &'a Client,
+    path: String,
+    query: Vec<(String, String)>,
+    cursor: Option<String>,
+    records: vec::IntoIter<AuditRecord>,
+    done: bool,
+}
+
+impl<'a> AuditRecordsIter<'a> {
+    fn new(client: &'a Client, path: String, query: &AuditRecordsQuery) -> AuditRecordsIter<'a> {
+        AuditRecordsIter {
+            client: client,
+            path: path,
+            query: query.query_pairs(),
+            cursor: None,
+            records: Vec::new().into_iter(),
+            done: false,
+        }
+    }
+
+    fn fetch_page(&mut self) -> Result<()> {
+        let mut query = self.query.clone();
+        query.push(("limit".into(), PAGE_LIMIT.to_string()));
+        if let Some(cursor) = self.cursor.take() {
+            query.push(("cursor".into(), cursor));
+        }
+
+        let page: AuditRecordsPage = self.client.get_json(&self.path, &query)?;
+        self.done = page.next_cursor.is_none() || page.records.is_empty();
+        self.cursor = page.next_cursor;
+        self.records = page.records.into_iter();
+        Ok(())
+    }
+}
+
+impl<'a> Iterator for AuditRecordsIter<'a> {
+    type Item = Result<AuditRecord>;
+
+    fn next(&mut self) -> Option<Result<AuditRecord>> {
+        loop {
+            if let Some(record) = self.records.next() {
+                return Some(Ok(record));
+            }
+            if self.done {
+                return None;
+            }
+            if let Err(err) = self.fetch_page() {
+                self.done = true;
+                return Some(Err(err));
+            }
+        }
+    }
 }
 
 
@@ -0,0 +1,220 @@
+use std::cmp;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use rest::v2::types::service_dependencies::{DependencyService, ServiceDependency};
+
+
+/// An in-memory view of service dependencies, used to answer blast-radius
+/// questions without further API calls. See
+/// `Client::service_dependency_graph` to load one from the API.
+#[derive(Debug, Default, PartialEq)]
+pub struct DependencyGraph {
+    /// Maps each service to the services it relies on.
+    supporting: BTreeMap<DependencyService, BTreeSet<DependencyService>>,
+
+    /// Maps each service to the services relying on it.
+    dependents: BTreeMap<DependencyService, BTreeSet<DependencyService>>,
+}
+
+impl DependencyGraph {
+    pub fn new() -> DependencyGraph {
+        DependencyGraph::default()
+    }
+
+    /// Add a service to the graph, even if it has no relationships.
+    pub fn add_service(&mut self, service: DependencyService) {
+        self.supporting.entry(service.clone()).or_insert_with(BTreeSet::new);
+        self.dependents.entry(service).or_insert_with(BTreeSet::new);
+    }
+
+    /// Add a relationship, and both of its services, to the graph.
+    pub fn add_dependency(&mut self, dependency: &ServiceDependency) {
+        let supporting = &dependency.supporting_service;
+        let dependent = &dependency.dependent_service;
+
+        self.add_service(supporting.clone());
+        self.add_service(dependent.clone());
+
+        self.supporting.get_mut(dependent).unwrap().insert(supporting.clone());
+        self.dependents.get_mut(supporting).unwrap().insert(dependent.clone());
+    }
+
+    /// All services in the graph.
+    pub fn services(&self) -> Vec<&DependencyService> {
+        self.supporting.keys().collect()
+    }
+
+    /// The services `service` directly relies on.
+    pub fn supporting_services(&self, service: &DependencyService) -> Vec<&DependencyService> {
+        self.supporting.get(service).map(|s| s.iter().collect()).unwrap_or_default()
+    }
+
+    /// The services directly relying on `service`.
+    pub fn dependent_services(&self, service: &DependencyService) -> Vec<&DependencyService> {
+        self.dependents.get(service).map(|s| s.iter().collect()).unwrap_or_default()
+    }
+
+    /// Every service that directly or transitively relies on `service`,
+    /// i.e. everything impacted if `service` goes down. `service` itself is
+    /// only included if it is part of a cycle.
+    pub fn impacted_by(&self, service: &DependencyService) -> Vec<&DependencyService> {
+        let mut impacted = BTreeSet::new();
+        let mut queue: VecDeque<&DependencyService> = VecDeque::new();
+        queue.extend(self.dependent_services(service));
+
+        while let Some(next) = queue.pop_front() {
+            if impacted.insert(next) {
+                queue.extend(self.dependent_services(next));
+            }
+        }
+
+        impacted.into_iter().collect()
+    }
+
+    /// Groups of services that depend on each other in a cycle. Each group
+    /// is a strongly connected component of more than one service, or a
+    /// single service that depends on itself.
+    pub fn cycles(&self) -> Vec<Vec<&DependencyService>> {
+        let mut tarjan = Tarjan {
+            graph: self,
+            index: 0,
+            indices: BTreeMap::new(),
+            lowlinks: BTreeMap::new(),
+            stack: Vec::new(),
+            on_stack: BTreeSet::new(),
+            components: Vec::new(),
+        };
+
+        for service in self.supporting.keys() {
+            if !tarjan.indices.contains_key(service) {
+                tarjan.visit(service);
+            }
+        }
+
+        let mut cycles: Vec<Vec<&DependencyService>> = tarjan.components.into_iter()
+            .filter(|component| {
+                component.len() > 1 || self.supporting[component[0]].contains(component[0])
+            })
+            .collect();
+        cycles.sort();
+        cycles
+    }
+}
+
+
+/// State for Tarjan's strongly connected components algorithm.
+struct Tarjan<'a> {
+    graph: &'a DependencyGraph,
+    index: usize,
+    indices: BTreeMap<&'a DependencyService, usize>,
+    lowlinks: BTreeMap<&'a DependencyService, usize>,
+    stack: Vec<&'a DependencyService>,
+    on_stack: BTreeSet<&'a DependencyService>,
+    components: Vec<Vec<&'a DependencyService>>,
+}
+
+impl<'a> Tarjan<'a> {
+    fn visit(&mut self, service: &'a DependencyService) {
+        self.indices.insert(service, self.index);
+        self.lowlinks.insert(service, self.index);
+        self.index += 1;
+        self.stack.push(service);
+        self.on_stack.insert(service);
+
+        for supporting in &self.graph.supporting[service] {
+            if !self.indices.contains_key(supporting) {
+                self.visit(supporting);
+                let lowlink = cmp::min(self.lowlinks[service], self.lowlinks[supporting]);
+                self.lowlinks.insert(service, lowlink);
+            } else if self.on_stack.contains(supporting) {
+                let lowlink = cmp::min(self.lowlinks[service], self.indices[supporting]);
+                self.lowlinks.insert(service, lowlink);
+            }
+        }
+
+        if self.lowlinks[service] == self.indices[service] {
+            let mut component = Vec::new();
+            loop {
+                let member = self.stack.pop().unwrap();
+                self.on_stack.remove(member);
+                component.push(member);
+                if member == service {
+                    break;
+                }
+            }
+            component.sort();
+            self.components.push(component);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn business(id: &str) -> DependencyService {
+        DependencyService::Business { id: id.into() }
+    }
+
+    fn technical(id: &str) -> DependencyService {
+        DependencyService::Technical { id: id.into() }
+    }
+
+    fn graph(edges: &[(DependencyService, DependencyService)]) -> DependencyGraph {
+        let mut graph = DependencyGraph::new();
+        for &(ref supporting, ref dependent) in edges {
+            graph.add_dependency(&ServiceDependency::new(supporting.clone(), dependent.clone()));
+        }
+        graph
+    }
+
+    #[test]
+    fn test_impacted_by() {
+        // db <- api <- banking
+        //          <- checkout <- storefront
+        let graph = graph(&[
+            (technical("db"), technical("api")),
+            (technical("api"), business("banking")),
+            (technical("api"), business("checkout")),
+            (business("checkout"), business("storefront")),
+        ]);
+
+        assert_eq!(
+            graph.impacted_by(&technical("db")),
+            vec![
+                &business("banking"),
+                &business("checkout"),
+                &business("storefront"),
+                &technical("api"),
+            ]
+        );
+        assert_eq!(graph.impacted_by(&business("storefront")), Vec::<&DependencyService>::new());
+        assert_eq!(graph.supporting_services(&business("checkout")), vec![&technical("api")]);
+        assert!(graph.cycles().is_empty());
+    }
+
+    #[test]
+    fn test_cycles() {
+        let graph = graph(&[
+            (technical("a"), technical("b")),
+            (technical("b"), technical("c")),
+            (technical("c"), technical("a")),
+            (technical("c"), business("d")),
+            (technical("e"), technical("e")),
+        ]);
+
+        assert_eq!(
+            graph.cycles(),
+            vec![
+                vec![&technical("a"), &technical("b"), &technical("c")],
+                vec![&technical("e")],
+            ]
+        );
+        assert_eq!(
+            graph.impacted_by(&technical("a")),
+            vec![&business("d"), &technical("a"), &technical("b"), &technical("c")]
+        );
+    }
+}
@@ -1,2 +1,3 @@
 pub mod client;
+pub mod dependency_graph;
 pub mod types;
@@ -0,0 +1,229 @@
+use serde_json;
+
+use ::events::v1::Context;
+use ::rest::v2::types::incidents::Incident;
+use ::rest::v2::types::services::Service;
+
+
+/// The body of an alert, as sent by the integration that created it.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct AlertBody {
+    /// Always `alert_body`.
+    #[serde(rename = "type")]
+    pub type_: String,
+
+    /// Contexts such as links or images sent along with the alert.
+    pub contexts: Vec<Context>,
+
+    /// An arbitrary JSON object containing any data explaining the nature
+    /// of the alert.
+    pub details: serde_json::Value,
+}
+
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "type")]
+pub enum Alert {
+    #[serde(rename="alert_reference")]
+    Reference {
+        id: String,
+
+        /// A short-form, server-generated string that provides succinct,
+        /// important information about an object suitable for primary
+        /// labeling of an entity in a client. In many cases, this will be
+        /// identical to `name`, though it is not intended to be an identifier.
+        summary: String,
+
+        /// The API show URL at which the object is accessible.
+        #[serde(rename="self")]
+        self_: String,
+
+        /// A URL at which the entity is uniquely displayed in the Web app.
+        html_url: Option<String>,
+    },
+
+    #[serde(rename="alert")]
+    Alert {
+        id: String,
+
+        /// A short-form, server-generated string that provides succinct,
+        /// important information about an object suitable for primary
+        /// labeling of an entity in a client. In many cases, this will be
+        /// identical to `name`, though it is not intended to be an identifier.
+        summary: String,
+
+        /// The API show URL at which the object is accessible.
+        #[serde(rename="self")]
+        self_: String,
+
+        /// A URL at which the entity is uniquely displayed in the Web app.
+        html_url: Option<String>,
+
+        /// The date/time the alert was first triggered.
+        created_at: String,
+
+        /// The current state of the alert.
+        /// Expected values include: `triggered` and `resolved`.
+        status: String,
+
+        /// The alert's de-duplication key.
+        alert_key: String,
+
+        /// The service with which the alert is associated.
+        service: Service,
+
+        /// The alert body, if the integration sent one.
+        body: Option<AlertBody>,
+
+        /// The incident to which the alert belongs.
+        incident: Incident,
+
+        /// Whether or not an alert is suppressed. Suppressed alerts are not
+        /// created with a parent incident.
+        suppressed: bool,
+
+        /// The magnitude of the problem as reported by the monitoring tool.
+        /// Expected values include:
+        /// `critical`, `error`, `warning`, and `info`
+        severity: String,
+    },
+}
+
+
+pub type Alerts = Vec<Alert>;
+
+
+/// The request body used to resolve an alert or move it to another incident.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct AlertUpdate {
+    /// Always `alert`.
+    #[serde(rename = "type")]
+    type_: String,
+
+    /// The new state of the alert. Only `resolved` is accepted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<String>,
+
+    /// The incident the alert should be moved to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    incident: Option<Incident>,
+}
+
+impl AlertUpdate {
+    /// An update that resolves the alert.
+    pub fn resolve() -> AlertUpdate {
+        AlertUpdate {
+            type_: "alert".into(),
+            status: Some("resolved".into()),
+            incident: None,
+        }
+    }
+
+    /// An update that moves the alert to the incident identified by
+    /// `incident_id`.
+    pub fn move_to<T: Into<String>>(incident_id: T) -> AlertUpdate {
+        AlertUpdate {
+            type_: "alert".into(),
+            status: None,
+            incident: Some(Incident::reference(incident_id)),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use serde_json;
+    use std::fs::File;
+    use std::io::Read;
+
+    #[test]
+    fn test_serde() {
+        let mut file = File::open("testdata/types/alerts.json").unwrap();
+        let mut data = String::new();
+        file.read_to_string(&mut data).unwrap();
+        let alerts: Alerts = serde_json::from_str(&data).unwrap();
+
+        // Verify deserialization.
+        assert_eq!(
+            alerts,
+            vec![
+                Alert::Reference {
+                    id: "PXPGF42".into(),
+                    summary: "The server is on fire.".into(),
+                    self_: "https://api.pagerduty.com/incidents/PT4KHLK/alerts/PXPGF42".into(),
+                    html_url: Some(
+                        "https://webdemo.pagerduty.com/alerts/PXPGF42".into()
+                    ),
+                },
+                Alert::Alert {
+                    id: "PQ8J1BF".into(),
+                    summary: "Disk usage on db01 is over 90%".into(),
+                    self_: "https://api.pagerduty.com/incidents/PT4KHLK/alerts/PQ8J1BF".into(),
+                    html_url: Some(
+                        "https://webdemo.pagerduty.com/alerts/PQ8J1BF".into()
+                    ),
+                    created_at: "2017-06-07T21:30:42Z".into(),
+                    status: "triggered".into(),
+                    alert_key: "baf7cf21b1da41b4b0221008339ff357".into(),
+                    service: Service::Reference {
+                        id: "PIJ90N7".into(),
+                        summary: "My Mail Service".into(),
+                        self_: "https://api.pagerduty.com/services/PIJ90N7".into(),
+                        html_url: Some(
+                            "https://webdemo.pagerduty.com/services/PIJ90N7".into()
+                        ),
+                    },
+                    body: Some(AlertBody {
+                        type_: "alert_body".into(),
+                        contexts: vec![
+                            Context::Link {
+                                href: "https://grafana.example.com/d/db01".into(),
+                                text: Some("Dashboard".into()),
+                            },
+                        ],
+                        details: json!({
+                            "host": "db01",
+                            "usage": 91.5,
+                        }),
+                    }),
+                    incident: Incident::Reference {
+                        id: "PT4KHLK".into(),
+                        summary: Some("[#1234] The server is on fire.".into()),
+                        self_: Some("https://api.pagerduty.com/incidents/PT4KHLK".into()),
+                        html_url: Some(
+                            "https://webdemo.pagerduty.com/incidents/PT4KHLK".into()
+                        ),
+                    },
+                    suppressed: false,
+                    severity: "critical".into(),
+                },
+            ]
+        );
+
+        // Verify that serialization round-trips.
+        let expected: serde_json::Value = serde_json::from_str(&data).unwrap();
+        let serialized: serde_json::Value = serde_json::from_str(
+            serde_json::to_string(&alerts).unwrap().as_ref()
+        ).unwrap();
+        assert_eq!(serialized, expected)
+    }
+
+    #[test]
+    fn test_alert_update_serialization() {
+        assert_eq!(
+            serde_json::to_value(&AlertUpdate::resolve()).unwrap(),
+            json!({"type": "alert", "status": "resolved"})
+        );
+
+        assert_eq!(
+            serde_json::to_value(&AlertUpdate::move_to("PT4KHLK")).unwrap(),
+            json!({
+                "type": "alert",
+                "incident": {"id": "PT4KHLK", "type": "incident_reference"},
+            })
+        );
+    }
+}
@@ -0,0 +1,540 @@
+/// The urgency of an incident.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Urgency {
+    High,
+    Low,
+}
+
+
+/// The time unit metrics are aggregated by.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum AggregateUnit {
+    Day,
+    Week,
+    Month,
+}
+
+
+/// The order analytics results are returned in.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Order {
+    Asc,
+    Desc,
+}
+
+
+/// Filters shared by all analytics requests. Every filter is optional,
+/// except for the date range which PagerDuty limits to one year.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct AnalyticsFilters {
+    /// Accepts an ISO 8601 date/time string. Only incidents created at or
+    /// after this time are included.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at_start: Option<String>,
+
+    /// Accepts an ISO 8601 date/time string. Only incidents created before
+    /// this time are included.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at_end: Option<String>,
+
+    /// Only include incidents with the given urgency.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub urgency: Option<Urgency>,
+
+    /// Only include major incidents when `true`, or exclude them when
+    /// `false`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub major: Option<bool>,
+
+    /// Only include incidents belonging to the given teams.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub team_ids: Option<Vec<String>>,
+
+    /// Only include incidents belonging to the given services.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_ids: Option<Vec<String>>,
+
+    /// Only include incidents with the given priorities.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority_ids: Option<Vec<String>>,
+}
+
+impl AnalyticsFilters {
+    pub fn new() -> AnalyticsFilters {
+        AnalyticsFilters::default()
+    }
+
+    /// Restrict results to incidents created in `[start, end)`.
+    pub fn date_range(mut self, start: String, end: String) -> AnalyticsFilters {
+        self.created_at_start = Some(start);
+        self.created_at_end = Some(end);
+        self
+    }
+
+    pub fn urgency(mut self, urgency: Urgency) -> AnalyticsFilters {
+        self.urgency = Some(urgency);
+        self
+    }
+
+    pub fn major(mut self, major: bool) -> AnalyticsFilters {
+        self.major = Some(major);
+        self
+    }
+
+    pub fn team_ids(mut self, team_ids: Vec<String>) -> AnalyticsFilters {
+        self.team_ids = Some(team_ids);
+        self
+    }
+
+    pub fn service_ids(mut self, service_ids: Vec<String>) -> AnalyticsFilters {
+        self.service_ids = Some(service_ids);
+        self
+    }
+
+    pub fn priority_ids(mut self, priority_ids: Vec<String>) -> AnalyticsFilters {
+        self.priority_ids = Some(priority_ids);
+        self
+    }
+}
+
+
+/// The request body for the `/analytics/metrics/incidents/*` endpoints.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct IncidentMetricsRequest {
+    filters: AnalyticsFilters,
+
+    /// When set, metrics are grouped into buckets of this unit, otherwise
+    /// a single aggregate covering the whole date range is returned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    aggregate_unit: Option<AggregateUnit>,
+
+    /// The time zone to use for the results and grouping.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time_zone: Option<String>,
+}
+
+impl IncidentMetricsRequest {
+    pub fn new(filters: AnalyticsFilters) -> IncidentMetricsRequest {
+        IncidentMetricsRequest {
+            filters: filters,
+            aggregate_unit: None,
+            time_zone: None,
+        }
+    }
+
+    pub fn aggregate_unit(mut self, aggregate_unit: AggregateUnit) -> IncidentMetricsRequest {
+        self.aggregate_unit = Some(aggregate_unit);
+        self
+    }
+
+    pub fn time_zone(mut self, time_zone: String) -> IncidentMetricsRequest {
+        self.time_zone = Some(time_zone);
+        self
+    }
+}
+
+
+/// Aggregated incident metrics, either for the whole account or for a
+/// single service or team, optionally for a single aggregation bucket.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct IncidentMetrics {
+    /// The start of the aggregation bucket, if an `aggregate_unit` was
+    /// requested.
+    pub range_start: Option<String>,
+
+    /// The service the metrics belong to, for per-service metrics.
+    pub service_id: Option<String>,
+
+    /// The name of the service, for per-service metrics.
+    pub service_name: Option<String>,
+
+    /// The team the metrics belong to, for per-team metrics.
+    pub team_id: Option<String>,
+
+    /// The name of the team, for per-team metrics.
+    pub team_name: Option<String>,
+
+    /// The mean number of times an incident was assigned.
+    pub mean_assignment_count: Option<f64>,
+
+    /// The mean engaged time with an incident, in seconds.
+    pub mean_engaged_seconds: Option<f64>,
+
+    /// The mean number of users who engaged with an incident.
+    pub mean_engaged_user_count: Option<f64>,
+
+    /// The mean time between the start of an incident and its first
+    /// acknowledgement, in seconds.
+    pub mean_seconds_to_first_ack: Option<f64>,
+
+    /// The mean time between the start of an incident and the first
+    /// responder engaging, in seconds.
+    pub mean_seconds_to_engage: Option<f64>,
+
+    /// The mean time between the start of an incident and the last
+    /// additional responder engaging, in seconds.
+    pub mean_seconds_to_mobilize: Option<f64>,
+
+    /// The mean time between the start and resolution of an incident, in
+    /// seconds.
+    pub mean_seconds_to_resolve: Option<f64>,
+
+    /// The total number of incidents.
+    pub total_incident_count: Option<u64>,
+
+    /// The total number of acknowledged incidents.
+    pub total_incidents_acknowledged: Option<u64>,
+
+    /// The total number of automatically resolved incidents.
+    pub total_incidents_auto_resolved: Option<u64>,
+
+    /// The total number of manually escalated incidents.
+    pub total_incidents_manual_escalated: Option<u64>,
+
+    /// The total number of incidents escalated due to a timeout.
+    pub total_incidents_timeout_escalated: Option<u64>,
+
+    /// The total number of major incidents.
+    pub total_major_incidents: Option<u64>,
+
+    /// The total number of escalations.
+    pub total_escalation_count: Option<u64>,
+
+    /// The total number of interruptions during business hours.
+    pub total_business_hour_interruptions: Option<u64>,
+
+    /// The total number of interruptions outside business hours.
+    pub total_off_hour_interruptions: Option<u64>,
+
+    /// The total number of interruptions during sleep hours.
+    pub total_sleep_hour_interruptions: Option<u64>,
+
+    /// The total engaged time across all incidents, in seconds.
+    pub total_engaged_seconds: Option<u64>,
+
+    /// The total time incidents spent snoozed, in seconds.
+    pub total_snoozed_seconds: Option<u64>,
+
+    /// The percentage of time during which no major incident was open.
+    pub up_time_pct: Option<f64>,
+}
+
+
+/// The response of the `/analytics/metrics/incidents/*` endpoints.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct IncidentMetricsResponse {
+    /// The filters the metrics were computed with.
+    pub filters: AnalyticsFilters,
+
+    /// The unit the metrics were aggregated by, if any.
+    pub aggregate_unit: Option<AggregateUnit>,
+
+    /// The time zone of the results.
+    pub time_zone: Option<String>,
+
+    /// The computed metrics.
+    pub data: Vec<IncidentMetrics>,
+}
+
+
+/// The request body for the `/analytics/raw/incidents` endpoint.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct RawIncidentsRequest {
+    filters: AnalyticsFilters,
+
+    /// The number of results to include in each batch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<u32>,
+
+    /// A cursor to request the next page of results, as returned in
+    /// `RawIncidentsResponse::last`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    starting_after: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    order: Option<Order>,
+
+    /// The field to order results by, e.g. `created_at` or
+    /// `seconds_to_resolve`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    order_by: Option<String>,
+
+    /// The time zone to use for the results.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time_zone: Option<String>,
+}
+
+impl RawIncidentsRequest {
+    pub fn new(filters: AnalyticsFilters) -> RawIncidentsRequest {
+        RawIncidentsRequest {
+            filters: filters,
+            limit: None,
+            starting_after: None,
+            order: None,
+            order_by: None,
+            time_zone: None,
+        }
+    }
+
+    pub fn limit(mut self, limit: u32) -> RawIncidentsRequest {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn starting_after(mut self, starting_after: String) -> RawIncidentsRequest {
+        self.starting_after = Some(starting_after);
+        self
+    }
+
+    pub fn order(mut self, order: Order, order_by: String) -> RawIncidentsRequest {
+        self.order = Some(order);
+        self.order_by = Some(order_by);
+        self
+    }
+
+    pub fn time_zone(mut self, time_zone: String) -> RawIncidentsRequest {
+        self.time_zone = Some(time_zone);
+        self
+    }
+}
+
+
+/// Metrics for a single incident.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct RawIncident {
+    pub id: String,
+
+    /// The number of the incident, unique across the account.
+    pub incident_number: u64,
+
+    /// The description of the incident.
+    pub description: Option<String>,
+
+    /// The date/time the incident was created.
+    pub created_at: String,
+
+    /// The date/time the incident was resolved, if it has been.
+    pub resolved_at: Option<String>,
+
+    pub service_id: String,
+
+    pub service_name: Option<String>,
+
+    pub team_id: Option<String>,
+
+    pub team_name: Option<String>,
+
+    pub escalation_policy_id: Option<String>,
+
+    pub urgency: Urgency,
+
+    /// Whether the incident was a major incident.
+    pub major: bool,
+
+    pub priority_id: Option<String>,
+
+    pub priority_name: Option<String>,
+
+    /// The number of times the incident was assigned.
+    pub assignment_count: Option<u64>,
+
+    /// The number of escalations of the incident.
+    pub escalation_count: Option<u64>,
+
+    /// The number of users who engaged with the incident.
+    pub engaged_user_count: Option<u64>,
+
+    /// The total engaged time with the incident, in seconds.
+    pub engaged_seconds: Option<u64>,
+
+    /// The time between the start of the incident and its first
+    /// acknowledgement, in seconds.
+    pub seconds_to_first_ack: Option<u64>,
+
+    /// The time between the start of the incident and the first responder
+    /// engaging, in seconds.
+    pub seconds_to_engage: Option<u64>,
+
+    /// The time between the start of the incident and the last additional
+    /// responder engaging, in seconds.
+    pub seconds_to_mobilize: Option<u64>,
+
+    /// The time between the start and resolution of the incident, in
+    /// seconds.
+    pub seconds_to_resolve: Option<u64>,
+
+    /// Whether the incident was resolved automatically.
+    pub auto_resolved: Option<bool>,
+}
+
+
+/// The response of the `/analytics/raw/incidents` endpoint.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct RawIncidentsResponse {
+    /// The filters the results were selected with.
+    pub filters: AnalyticsFilters,
+
+    /// The incidents in this page of results.
+    pub data: Vec<RawIncident>,
+
+    /// The cursor of the first result in this page.
+    pub first: Option<String>,
+
+    /// The cursor of the last result in this page. Pass it to
+    /// `RawIncidentsRequest::starting_after` to fetch the next page.
+    pub last: Option<String>,
+
+    /// Whether more results are available.
+    pub more: bool,
+
+    /// The page size used.
+    pub limit: u32,
+
+    pub order: Option<Order>,
+
+    pub order_by: Option<String>,
+
+    pub time_zone: Option<String>,
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use serde_json;
+    use std::fs::File;
+    use std::io::Read;
+
+    #[test]
+    fn test_serde() {
+        let mut file = File::open("testdata/types/analytics_incident_metrics.json").unwrap();
+        let mut data = String::new();
+        file.read_to_string(&mut data).unwrap();
+        let response: IncidentMetricsResponse = serde_json::from_str(&data).unwrap();
+
+        // Verify deserialization.
+        assert_eq!(
+            response,
+            IncidentMetricsResponse {
+                filters: AnalyticsFilters::new()
+                    .date_range(
+                        "2020-06-01T00:00:00Z".into(),
+                        "2020-06-15T00:00:00Z".into()
+                    )
+                    .urgency(Urgency::High)
+                    .service_ids(vec!["PIJ90N7".into()]),
+                aggregate_unit: Some(AggregateUnit::Week),
+                time_zone: Some("Etc/UTC".into()),
+                data: vec![
+                    IncidentMetrics {
+                        range_start: Some("2020-06-01T00:00:00Z".into()),
+                        service_id: Some("PIJ90N7".into()),
+                        service_name: Some("My Mail Service".into()),
+                        team_id: None,
+                        team_name: None,
+                        mean_assignment_count: Some(1.5),
+                        mean_engaged_seconds: Some(1200.0),
+                        mean_engaged_user_count: Some(2.0),
+                        mean_seconds_to_first_ack: Some(95.5),
+                        mean_seconds_to_engage: Some(120.0),
+                        mean_seconds_to_mobilize: Some(300.0),
+                        mean_seconds_to_resolve: Some(3600.0),
+                        total_incident_count: Some(4),
+                        total_incidents_acknowledged: Some(4),
+                        total_incidents_auto_resolved: Some(1),
+                        total_incidents_manual_escalated: Some(0),
+                        total_incidents_timeout_escalated: Some(1),
+                        total_major_incidents: Some(0),
+                        total_escalation_count: Some(1),
+                        total_business_hour_interruptions: Some(2),
+                        total_off_hour_interruptions: Some(1),
+                        total_sleep_hour_interruptions: Some(1),
+                        total_engaged_seconds: Some(4800),
+                        total_snoozed_seconds: Some(0),
+                        up_time_pct: Some(100.0),
+                    },
+                    IncidentMetrics {
+                        range_start: Some("2020-06-08T00:00:00Z".into()),
+                        service_id: Some("PIJ90N7".into()),
+                        service_name: Some("My Mail Service".into()),
+                        team_id: None,
+                        team_name: None,
+                        mean_assignment_count: None,
+                        mean_engaged_seconds: None,
+                        mean_engaged_user_count: None,
+                        mean_seconds_to_first_ack: None,
+                        mean_seconds_to_engage: None,
+                        mean_seconds_to_mobilize: None,
+                        mean_seconds_to_resolve: None,
+                        total_incident_count: Some(0),
+                        total_incidents_acknowledged: Some(0),
+                        total_incidents_auto_resolved: Some(0),
+                        total_incidents_manual_escalated: Some(0),
+                        total_incidents_timeout_escalated: Some(0),
+                        total_major_incidents: Some(0),
+                        total_escalation_count: Some(0),
+                        total_business_hour_interruptions: Some(0),
+                        total_off_hour_interruptions: Some(0),
+                        total_sleep_hour_interruptions: Some(0),
+                        total_engaged_seconds: Some(0),
+                        total_snoozed_seconds: Some(0),
+                        up_time_pct: Some(100.0),
+                    },
+                ],
+            }
+        );
+
+        // Verify that serialization round-trips.
+        let expected: serde_json::Value = serde_json::from_str(&data).unwrap();
+        let serialized: serde_json::Value = serde_json::from_str(
+            serde_json::to_string(&response).unwrap().as_ref()
+        ).unwrap();
+        assert_eq!(serialized, expected)
+    }
+
+    #[test]
+    fn test_request_serialization() {
+        let filters = AnalyticsFilters::new()
+            .date_range("2020-06-01T00:00:00Z".into(), "2020-06-08T00:00:00Z".into())
+            .team_ids(vec!["P7W0ZIU".into()]);
+
+        assert_eq!(
+            serde_json::to_value(
+                &IncidentMetricsRequest::new(filters.clone())
+                    .aggregate_unit(AggregateUnit::Day)
+                    .time_zone("America/Los_Angeles".into())
+            ).unwrap(),
+            json!({
+                "filters": {
+                    "created_at_start": "2020-06-01T00:00:00Z",
+                    "created_at_end": "2020-06-08T00:00:00Z",
+                    "team_ids": ["P7W0ZIU"],
+                },
+                "aggregate_unit": "day",
+                "time_zone": "America/Los_Angeles",
+            })
+        );
+
+        assert_eq!(
+            serde_json::to_value(
+                &RawIncidentsRequest::new(filters)
+                    .limit(50)
+                    .order(Order::Desc, "seconds_to_resolve".into())
+            ).unwrap(),
+            json!({
+                "filters": {
+                    "created_at_start": "2020-06-01T00:00:00Z",
+                    "created_at_end": "2020-06-08T00:00:00Z",
+                    "team_ids": ["P7W0ZIU"],
+                },
+                "limit": 50,
+                "order": "desc",
+                "order_by": "seconds_to_resolve",
+            })
+        );
+    }
+}
@@ -0,0 +1,347 @@
+// Re-exported from its old home here, now that other types use it too.
+pub use ::rest::v2::types::references::ResourceReference;
+
+
+/// Information about the request that caused the audited change.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ExecutionContext {
+    /// The id of the request that performed the action.
+    pub request_id: Option<String>,
+
+    /// The IP address the request came from.
+    pub remote_address: Option<String>,
+}
+
+
+/// How the audited action was performed.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct AuditMethod {
+    /// The method used to perform the action.
+    /// Expected values include:
+    /// `browser`, `oauth`, `api_token`, `identity_provider` and `other`.
+    #[serde(rename = "type")]
+    pub type_: String,
+
+    /// A description of the method.
+    pub description: Option<String>,
+
+    /// The last few characters of the token used, if any.
+    pub truncated_token: Option<String>,
+}
+
+
+/// A single field that changed as part of the audited action.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct FieldChange {
+    /// The name of the field.
+    pub name: String,
+
+    /// A description of the field.
+    pub description: Option<String>,
+
+    /// The new value of the field.
+    pub value: Option<String>,
+
+    /// The value of the field before the change.
+    pub before_value: Option<String>,
+}
+
+
+/// References that were added to or removed from the resource as part of
+/// the audited action, e.g. users added to a team.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ReferenceChange {
+    /// The name of the reference field.
+    pub name: String,
+
+    /// A description of the reference field.
+    pub description: Option<String>,
+
+    /// References added by the action.
+    pub added: Option<Vec<ResourceReference>>,
+
+    /// References removed by the action.
+    pub removed: Option<Vec<ResourceReference>>,
+}
+
+
+/// Field-level details of the audited change.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct AuditDetails {
+    /// The resource that was changed.
+    pub resource: ResourceReference,
+
+    /// The fields of the resource that changed.
+    pub fields: Option<Vec<FieldChange>>,
+
+    /// The references of the resource that changed.
+    pub references: Option<Vec<ReferenceChange>>,
+}
+
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct AuditRecord {
+    pub id: String,
+
+    /// The API show URL at which the object is accessible.
+    #[serde(rename = "self")]
+    pub self_: String,
+
+    /// The date/time the action was performed.
+    pub execution_time: String,
+
+    /// Information about the request that performed the action.
+    pub execution_context: Option<ExecutionContext>,
+
+    /// The users, API keys or apps that performed the action.
+    pub actors: Vec<ResourceReference>,
+
+    /// How the action was performed.
+    pub method: AuditMethod,
+
+    /// The top-level resource that was changed.
+    pub root_resource: ResourceReference,
+
+    /// The action that was performed.
+    /// Expected values include: `create`, `update` and `delete`.
+    pub action: String,
+
+    /// The changes made by the action, if any were recorded.
+    pub details: Option<AuditDetails>,
+}
+
+
+pub type AuditRecords = Vec<AuditRecord>;
+
+
+/// Filters applied when listing audit records.
+///
+/// The per-entity audit trails (users, teams, schedules and escalation
+/// policies) only honor `since` and `until`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AuditRecordsQuery {
+    since: Option<String>,
+    until: Option<String>,
+    root_resource_types: Vec<String>,
+    actions: Vec<String>,
+    actor_type: Option<String>,
+    actor_id: Option<String>,
+    method_type: Option<String>,
+}
+
+impl AuditRecordsQuery {
+    pub fn new() -> AuditRecordsQuery {
+        AuditRecordsQuery::default()
+    }
+
+    /// The start of the date range to search, as an ISO 8601 date/time.
+    pub fn since(mut self, since: String) -> AuditRecordsQuery {
+        self.since = Some(since);
+        self
+    }
+
+    /// The end of the date range to search, as an ISO 8601 date/time.
+    pub fn until(mut self, until: String) -> AuditRecordsQuery {
+        self.until = Some(until);
+        self
+    }
+
+    /// Only include records whose root resource is of the given type,
+    /// e.g. `users`, `teams`, `schedules` or `escalation_policies`.
+    pub fn root_resource_type(mut self, root_resource_type: String) -> AuditRecordsQuery {
+        self.root_resource_types.push(root_resource_type);
+        self
+    }
+
+    /// Only include records for the given action, e.g. `create`, `update`
+    /// or `delete`.
+    pub fn action(mut self, action: String) -> AuditRecordsQuery {
+        self.actions.push(action);
+        self
+    }
+
+    /// Only include records performed by the given actor. `actor_type` is
+    /// one of `user_reference`, `api_key_reference` or `app_reference`.
+    pub fn actor(mut self, actor_type: String, actor_id: String) -> AuditRecordsQuery {
+        self.actor_type = Some(actor_type);
+        self.actor_id = Some(actor_id);
+        self
+    }
+
+    /// Only include records performed via the given method type, e.g.
+    /// `browser` or `api_token`.
+    pub fn method_type(mut self, method_type: String) -> AuditRecordsQuery {
+        self.method_type = Some(method_type);
+        self
+    }
+
+    pub(crate) fn query_pairs(&self) -> Vec<(String, String)> {
+        let mut pairs = Vec::new();
+        if let Some(ref since) = self.since {
+            pairs.push(("since".into(), since.clone()));
+        }
+        if let Some(ref until) = self.until {
+            pairs.push(("until".into(), until.clone()));
+        }
+        for root_resource_type in &self.root_resource_types {
+            pairs.push(("root_resource_types[]".into(), root_resource_type.clone()));
+        }
+        for action in &self.actions {
+            pairs.push(("actions[]".into(), action.clone()));
+        }
+        if let Some(ref actor_type) = self.actor_type {
+            pairs.push(("actor_type".into(), actor_type.clone()));
+        }
+        if let Some(ref actor_id) = self.actor_id {
+            pairs.push(("actor_id".into(), actor_id.clone()));
+        }
+        if let Some(ref method_type) = self.method_type {
+            pairs.push(("method_type".into(), method_type.clone()));
+        }
+        pairs
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use serde_json;
+    use std::fs::File;
+    use std::io::Read;
+
+    #[test]
+    fn test_serde() {
+        let mut file = File::open("testdata/types/audit_records.json").unwrap();
+        let mut data = String::new();
+        file.read_to_string(&mut data).unwrap();
+        let records: AuditRecords = serde_json::from_str(&data).unwrap();
+
+        // Verify deserialization.
+        assert_eq!(
+            records,
+            vec![
+                AuditRecord {
+                    id: "PDRECORDID1_TEAM_UPDATED".into(),
+                    self_: "https://api.pagerduty.com/audit/records/PDRECORDID1_TEAM_UPDATED".into(),
+                    execution_time: "2020-06-04T15:25:08.688Z".into(),
+                    execution_context: Some(ExecutionContext {
+                        request_id: Some("100sz3dde-dd4c-4b31-8f24-3e7a9c0d1f2e".into()),
+                        remote_address: Some("201.19.20.19".into()),
+                    }),
+                    actors: vec![
+                        ResourceReference {
+                            id: "P5T36BU".into(),
+                            type_: "user_reference".into(),
+                            summary: Some("abhijit@pagerduty.com".into()),
+                            self_: Some("https://api.pagerduty.com/users/P5T36BU".into()),
+                            html_url: Some("https://webdemo.pagerduty.com/users/P5T36BU".into()),
+                        },
+                    ],
+                    method: AuditMethod {
+                        type_: "api_token".into(),
+                        description: Some("Public API".into()),
+                        truncated_token: Some("3xyz".into()),
+                    },
+                    root_resource: ResourceReference {
+                        id: "P7W0ZIU".into(),
+                        type_: "team_reference".into(),
+                        summary: Some("Monitoring Tools Team".into()),
+                        self_: Some("https://api.pagerduty.com/teams/P7W0ZIU".into()),
+                        html_url: Some("https://webdemo.pagerduty.com/teams/P7W0ZIU".into()),
+                    },
+                    action: "update".into(),
+                    details: Some(AuditDetails {
+                        resource: ResourceReference {
+                            id: "P7W0ZIU".into(),
+                            type_: "team_reference".into(),
+                            summary: Some("Monitoring Tools Team".into()),
+                            self_: None,
+                            html_url: None,
+                        },
+                        fields: Some(vec![
+                            FieldChange {
+                                name: "name".into(),
+                                description: Some("The name of the team".into()),
+                                value: Some("Monitoring Tools Team".into()),
+                                before_value: Some("Monitoring Team".into()),
+                            },
+                        ]),
+                        references: Some(vec![
+                            ReferenceChange {
+                                name: "members".into(),
+                                description: None,
+                                added: Some(vec![
+                                    ResourceReference {
+                                        id: "PRMXSSO".into(),
+                                        type_: "user_reference".into(),
+                                        summary: Some("Benedict Cumberbatch".into()),
+                                        self_: None,
+                                        html_url: None,
+                                    },
+                                ]),
+                                removed: None,
+                            },
+                        ]),
+                    }),
+                },
+                AuditRecord {
+                    id: "PDRECORDID2_USER_DELETED".into(),
+                    self_: "https://api.pagerduty.com/audit/records/PDRECORDID2_USER_DELETED".into(),
+                    execution_time: "2020-06-05T09:10:11.000Z".into(),
+                    execution_context: None,
+                    actors: vec![
+                        ResourceReference {
+                            id: "PAPIKEY".into(),
+                            type_: "api_key_reference".into(),
+                            summary: Some("Terraform".into()),
+                            self_: None,
+                            html_url: None,
+                        },
+                    ],
+                    method: AuditMethod {
+                        type_: "api_token".into(),
+                        description: None,
+                        truncated_token: None,
+                    },
+                    root_resource: ResourceReference {
+                        id: "PRMXSSO".into(),
+                        type_: "user_reference".into(),
+                        summary: Some("Benedict Cumberbatch".into()),
+                        self_: Some("https://api.pagerduty.com/users/PRMXSSO".into()),
+                        html_url: None,
+                    },
+                    action: "delete".into(),
+                    details: None,
+                },
+            ]
+        );
+
+        // Verify that serialization round-trips.
+        let expected: serde_json::Value = serde_json::from_str(&data).unwrap();
+        let serialized: serde_json::Value = serde_json::from_str(
+            serde_json::to_string(&records).unwrap().as_ref()
+        ).unwrap();
+        assert_eq!(serialized, expected)
+    }
+
+    #[test]
+    fn test_query_pairs() {
+        let query = AuditRecordsQuery::new()
+            .since("2020-06-01T00:00:00Z".into())
+            .root_resource_type("teams".into())
+            .root_resource_type("users".into())
+            .action("delete".into());
+
+        assert_eq!(
+            query.query_pairs(),
+            vec![
+                ("since".into(), "2020-06-01T00:00:00Z".into()),
+                ("root_resource_types[]".into(), "teams".into()),
+                ("root_resource_types[]".into(), "users".into()),
+                ("actions[]".into(), "delete".into()),
+            ]
+        );
+    }
+}
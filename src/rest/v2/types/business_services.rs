@@ -0,0 +1,113 @@
+use ::rest::v2::types::references::ResourceReference;
+
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "type")]
+pub enum BusinessService {
+    #[serde(rename="business_service_reference")]
+    Reference {
+        id: String,
+
+        /// A short-form, server-generated string that provides succinct,
+        /// important information about an object suitable for primary
+        /// labeling of an entity in a client. In many cases, this will be
+        /// identical to `name`, though it is not intended to be an identifier.
+        summary: String,
+
+        /// The API show URL at which the object is accessible.
+        #[serde(rename="self")]
+        self_: String,
+
+        /// A URL at which the entity is uniquely displayed in the Web app.
+        html_url: Option<String>,
+    },
+
+    #[serde(rename="business_service")]
+    BusinessService {
+        id: String,
+
+        /// A short-form, server-generated string that provides succinct,
+        /// important information about an object suitable for primary
+        /// labeling of an entity in a client. In many cases, this will be
+        /// identical to `name`, though it is not intended to be an identifier.
+        summary: String,
+
+        /// The API show URL at which the object is accessible.
+        #[serde(rename="self")]
+        self_: String,
+
+        /// A URL at which the entity is uniquely displayed in the Web app.
+        html_url: Option<String>,
+
+        /// The name of the business service.
+        name: String,
+
+        /// The description of the business service.
+        description: Option<String>,
+
+        /// The owner of the business service.
+        point_of_contact: Option<String>,
+
+        /// The team that owns the business service.
+        team: Option<ResourceReference>,
+    },
+}
+
+
+pub type BusinessServices = Vec<BusinessService>;
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use serde_json;
+    use std::fs::File;
+    use std::io::Read;
+
+    #[test]
+    fn test_serde() {
+        let mut file = File::open("testdata/types/business_services.json").unwrap();
+        let mut data = String::new();
+        file.read_to_string(&mut data).unwrap();
+        let business_services: BusinessServices = serde_json::from_str(&data).unwrap();
+
+        // Verify deserialization.
+        assert_eq!(
+            business_services,
+            vec![
+                BusinessService::Reference {
+                    id: "PBZUP2B".into(),
+                    summary: "Checkout".into(),
+                    self_: "https://api.pagerduty.com/business_services/PBZUP2B".into(),
+                    html_url: None,
+                },
+                BusinessService::BusinessService {
+                    id: "P4IWPQD".into(),
+                    summary: "Online Banking".into(),
+                    self_: "https://api.pagerduty.com/business_services/P4IWPQD".into(),
+                    html_url: Some(
+                        "https://webdemo.pagerduty.com/business_services/P4IWPQD".into()
+                    ),
+                    name: "Online Banking".into(),
+                    description: Some("Customer facing banking portal".into()),
+                    point_of_contact: Some("banking@example.com".into()),
+                    team: Some(ResourceReference {
+                        id: "P7W0ZIU".into(),
+                        type_: "team_reference".into(),
+                        summary: None,
+                        self_: Some("https://api.pagerduty.com/teams/P7W0ZIU".into()),
+                        html_url: None,
+                    }),
+                },
+            ]
+        );
+
+        // Verify that serialization round-trips.
+        let expected: serde_json::Value = serde_json::from_str(&data).unwrap();
+        let serialized: serde_json::Value = serde_json::from_str(
+            serde_json::to_string(&business_services).unwrap().as_ref()
+        ).unwrap();
+        assert_eq!(serialized, expected)
+    }
+}
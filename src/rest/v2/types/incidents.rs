@@ -0,0 +1,36 @@
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "type")]
+pub enum Incident {
+    #[serde(rename="incident_reference")]
+    Reference {
+        id: String,
+
+        /// A short-form, server-generated string that provides succinct,
+        /// important information about an object suitable for primary
+        /// labeling of an entity in a client. In many cases, this will be
+        /// identical to `name`, though it is not intended to be an identifier.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        summary: Option<String>,
+
+        /// The API show URL at which the object is accessible.
+        #[serde(rename="self", skip_serializing_if = "Option::is_none")]
+        self_: Option<String>,
+
+        /// A URL at which the entity is uniquely displayed in the Web app.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        html_url: Option<String>,
+    },
+}
+
+impl Incident {
+    /// Creates a bare reference to an incident, as used in request bodies
+    /// where only the `id` is required.
+    pub fn reference<T: Into<String>>(id: T) -> Incident {
+        Incident::Reference {
+            id: id.into(),
+            summary: None,
+            self_: None,
+            html_url: None,
+        }
+    }
+}
@@ -1,5 +1,14 @@
 pub mod abilities;
+pub mod alerts;
+pub mod analytics;
+pub mod audit_records;
+pub mod business_services;
 pub mod contact_methods;
+pub mod incidents;
 pub mod notification_rules;
+pub mod references;
+pub mod service_dependencies;
+pub mod services;
 pub mod teams;
 pub mod users;
+pub mod webhook_subscriptions;
@@ -0,0 +1,27 @@
+/// A reference to any PagerDuty object. Used where a field can point at
+/// many different kinds of resources, so the `type` is kept as a plain string.
+///
+/// Expected values for `type_` include:
+/// `user_reference`, `team_reference`, `schedule_reference`,
+/// `escalation_policy_reference`, `service_reference`, `api_key_reference`
+/// and `app_reference`.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ResourceReference {
+    pub id: String,
+
+    #[serde(rename = "type")]
+    pub type_: String,
+
+    /// A short-form, server-generated string that provides succinct,
+    /// important information about an object suitable for primary
+    /// labeling of an entity in a client. In many cases, this will be
+    /// identical to `name`, though it is not intended to be an identifier.
+    pub summary: Option<String>,
+
+    /// The API show URL at which the object is accessible.
+    #[serde(rename = "self")]
+    pub self_: Option<String>,
+
+    /// A URL at which the entity is uniquely displayed in the Web app.
+    pub html_url: Option<String>,
+}
@@ -0,0 +1,110 @@
+/// One end of a service dependency. Business services and technical
+/// services can both support, or depend on, each other.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+#[serde(tag = "type")]
+pub enum DependencyService {
+    #[serde(rename="business_service_reference")]
+    Business {
+        id: String,
+    },
+
+    #[serde(rename="technical_service_reference")]
+    Technical {
+        id: String,
+    },
+}
+
+impl DependencyService {
+    pub fn id(&self) -> &str {
+        match *self {
+            DependencyService::Business { ref id } => id,
+            DependencyService::Technical { ref id } => id,
+        }
+    }
+}
+
+
+/// A relationship in which `dependent_service` relies on
+/// `supporting_service`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct ServiceDependency {
+    /// The id of the relationship. Not set when associating new
+    /// dependencies.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    /// Always `service_dependency`. Not set when associating new
+    /// dependencies.
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+
+    /// The service that is relied upon.
+    pub supporting_service: DependencyService,
+
+    /// The service that relies on `supporting_service`.
+    pub dependent_service: DependencyService,
+}
+
+impl ServiceDependency {
+    /// A new relationship, as used when associating or disassociating
+    /// dependencies.
+    pub fn new(
+        supporting_service: DependencyService, dependent_service: DependencyService
+    ) -> ServiceDependency {
+        ServiceDependency {
+            id: None,
+            type_: None,
+            supporting_service: supporting_service,
+            dependent_service: dependent_service,
+        }
+    }
+}
+
+
+pub type ServiceDependencies = Vec<ServiceDependency>;
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use serde_json;
+    use std::fs::File;
+    use std::io::Read;
+
+    #[test]
+    fn test_serde() {
+        let mut file = File::open("testdata/types/service_dependencies.json").unwrap();
+        let mut data = String::new();
+        file.read_to_string(&mut data).unwrap();
+        let dependencies: ServiceDependencies = serde_json::from_str(&data).unwrap();
+
+        // Verify deserialization.
+        assert_eq!(
+            dependencies,
+            vec![
+                ServiceDependency {
+                    id: Some("D4RTQXAN4ZD2XZ0E4UEGYPXFBNCHQ7".into()),
+                    type_: Some("service_dependency".into()),
+                    supporting_service: DependencyService::Technical {
+                        id: "PIJ90N7".into(),
+                    },
+                    dependent_service: DependencyService::Business {
+                        id: "P4IWPQD".into(),
+                    },
+                },
+                ServiceDependency::new(
+                    DependencyService::Business { id: "PBZUP2B".into() },
+                    DependencyService::Business { id: "P4IWPQD".into() },
+                ),
+            ]
+        );
+
+        // Verify that serialization round-trips.
+        let expected: serde_json::Value = serde_json::from_str(&data).unwrap();
+        let serialized: serde_json::Value = serde_json::from_str(
+            serde_json::to_string(&dependencies).unwrap().as_ref()
+        ).unwrap();
+        assert_eq!(serialized, expected)
+    }
+}
@@ -0,0 +1,23 @@
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "type")]
+pub enum Service {
+    #[serde(rename="service_reference")]
+    Reference {
+        id: String,
+
+        /// A short-form, server-generated string that provides succinct,
+        /// important information about an object suitable for primary
+        /// labeling of an entity in a client. In many cases, this will be
+        /// identical to `name`, though it is not intended to be an identifier.
+        summary: String,
+
+        /// The API show URL at which the object is accessible.
+        #[serde(rename="self")]
+        self_: String,
+
+        /// A URL at which the entity is uniquely displayed in the Web app.
+        html_url: Option<String>,
+    },
+}
+
+pub type Services = Vec<Service>;
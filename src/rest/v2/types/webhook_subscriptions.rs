@@ -0,0 +1,250 @@
+/// A custom header sent along with every webhook delivery.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct CustomHeader {
+    pub name: String,
+    pub value: String,
+}
+
+
+/// How webhook events are delivered to the receiver.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(tag = "type")]
+pub enum DeliveryMethod {
+    #[serde(rename="http_delivery_method")]
+    Http {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+
+        /// The secret used to sign webhook payloads. Only returned when the
+        /// subscription is created.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        secret: Option<String>,
+
+        /// Whether PagerDuty has temporarily disabled delivery after
+        /// repeated failures.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        temporarily_disabled: Option<bool>,
+
+        /// The destination URL for webhook delivery.
+        url: String,
+
+        /// Optional headers to be set on this webhook subscription when
+        /// sent.
+        #[serde(default)]
+        custom_headers: Vec<CustomHeader>,
+    },
+}
+
+
+/// Determines which events will match and produce a webhook payload.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(tag = "type")]
+pub enum WebhookFilter {
+    /// Events from every service in the account.
+    #[serde(rename="account_reference")]
+    Account {},
+
+    /// Events from the service with the given id.
+    #[serde(rename="service_reference")]
+    Service {
+        id: String,
+    },
+
+    /// Events from services owned by the team with the given id.
+    #[serde(rename="team_reference")]
+    Team {
+        id: String,
+    },
+}
+
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct WebhookSubscription {
+    /// Not set when creating a subscription.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    /// Always `webhook_subscription`.
+    #[serde(rename = "type")]
+    pub type_: String,
+
+    /// Whether the subscription will produce webhook events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active: Option<bool>,
+
+    pub delivery_method: DeliveryMethod,
+
+    /// A short description of the webhook subscription.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// The set of outbound event types the webhook will receive, e.g.
+    /// `incident.triggered` or `incident.resolved`.
+    pub events: Vec<String>,
+
+    pub filter: WebhookFilter,
+}
+
+impl WebhookSubscription {
+    /// A new subscription delivering `events` matching `filter` to `url`
+    /// over HTTP.
+    pub fn new(url: String, events: Vec<String>, filter: WebhookFilter) -> WebhookSubscription {
+        WebhookSubscription {
+            id: None,
+            type_: "webhook_subscription".into(),
+            active: None,
+            delivery_method: DeliveryMethod::Http {
+                id: None,
+                secret: None,
+                temporarily_disabled: None,
+                url: url,
+                custom_headers: vec![],
+            },
+            description: None,
+            events: events,
+            filter: filter,
+        }
+    }
+
+    pub fn description(mut self, description: String) -> WebhookSubscription {
+        self.description = Some(description);
+        self
+    }
+
+    pub fn active(mut self, active: bool) -> WebhookSubscription {
+        self.active = Some(active);
+        self
+    }
+
+    pub fn custom_header(mut self, name: String, value: String) -> WebhookSubscription {
+        match self.delivery_method {
+            DeliveryMethod::Http { ref mut custom_headers, .. } => {
+                custom_headers.push(CustomHeader { name: name, value: value });
+            },
+        }
+        self
+    }
+}
+
+
+pub type WebhookSubscriptions = Vec<WebhookSubscription>;
+
+
+/// The request body used to update a subscription. Only the fields that
+/// are set are changed.
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct WebhookSubscriptionUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    active: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    events: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filter: Option<WebhookFilter>,
+}
+
+impl WebhookSubscriptionUpdate {
+    pub fn new() -> WebhookSubscriptionUpdate {
+        WebhookSubscriptionUpdate::default()
+    }
+
+    pub fn active(mut self, active: bool) -> WebhookSubscriptionUpdate {
+        self.active = Some(active);
+        self
+    }
+
+    pub fn description(mut self, description: String) -> WebhookSubscriptionUpdate {
+        self.description = Some(description);
+        self
+    }
+
+    pub fn events(mut self, events: Vec<String>) -> WebhookSubscriptionUpdate {
+        self.events = Some(events);
+        self
+    }
+
+    pub fn filter(mut self, filter: WebhookFilter) -> WebhookSubscriptionUpdate {
+        self.filter = Some(filter);
+        self
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use serde_json;
+    use std::fs::File;
+    use std::io::Read;
+
+    #[test]
+    fn test_serde() {
+        let mut file = File::open("testdata/types/webhook_subscriptions.json").unwrap();
+        let mut data = String::new();
+        file.read_to_string(&mut data).unwrap();
+        let subscriptions: WebhookSubscriptions = serde_json::from_str(&data).unwrap();
+
+        // Verify deserialization.
+        assert_eq!(
+            subscriptions,
+            vec![
+                WebhookSubscription {
+                    id: Some("PY1OHUB".into()),
+                    type_: "webhook_subscription".into(),
+                    active: Some(true),
+                    delivery_method: DeliveryMethod::Http {
+                        id: Some("PF9KMXH".into()),
+                        secret: None,
+                        temporarily_disabled: Some(false),
+                        url: "https://example.com/receive_a_pagerduty_webhook".into(),
+                        custom_headers: vec![
+                            CustomHeader {
+                                name: "X-Receiver".into(),
+                                value: "incident-bot".into(),
+                            },
+                        ],
+                    },
+                    description: Some("Sends PagerDuty v3 webhook events somewhere interesting.".into()),
+                    events: vec![
+                        "incident.acknowledged".into(),
+                        "incident.resolved".into(),
+                        "incident.triggered".into(),
+                    ],
+                    filter: WebhookFilter::Service { id: "PIJ90N7".into() },
+                },
+                WebhookSubscription::new(
+                    "https://example.com/account_webhook".into(),
+                    vec!["incident.triggered".into()],
+                    WebhookFilter::Account {},
+                ),
+            ]
+        );
+
+        // Verify that serialization round-trips.
+        let expected: serde_json::Value = serde_json::from_str(&data).unwrap();
+        let serialized: serde_json::Value = serde_json::from_str(
+            serde_json::to_string(&subscriptions).unwrap().as_ref()
+        ).unwrap();
+        assert_eq!(serialized, expected)
+    }
+
+    #[test]
+    fn test_update_serialization() {
+        let update = WebhookSubscriptionUpdate::new()
+            .active(false)
+            .filter(WebhookFilter::Team { id: "P7W0ZIU".into() });
+
+        assert_eq!(
+            serde_json::to_value(&update).unwrap(),
+            json!({
+                "active": false,
+                "filter": {"type": "team_reference", "id": "P7W0ZIU"},
+            })
+        );
+    }
+}
@@ -0,0 +1,119 @@
+//! The panic hook is process-wide, so its tests run in their own binary
+//! where no other test panics.
+
+#![cfg(feature = "panic-hook")]
+
+extern crate pagersduty;
+extern crate serde_json;
+
+use std::sync::{Arc, Barrier, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use pagersduty::events::{CaptureSink, EventSink, PanicHookConfig, install_panic_hook, set_default_sink};
+use pagersduty::events::{v1, v2};
+use pagersduty::events::v1::ErrorResponse;
+use serde_json::Value;
+
+/// A sink that doesn't send anything until `gate` is unlocked.
+#[derive(Default)]
+struct GatedSink {
+    gate: Mutex<()>,
+    sent: CaptureSink,
+}
+
+impl EventSink for GatedSink {
+    fn send_v1(&self, event: &Value) -> Result<v1::EventProcessed, ErrorResponse> {
+        let _gate = self.gate.lock().unwrap();
+        self.sent.send_v1(event)
+    }
+
+    fn send_v2(&self, event: &Value) -> Result<v2::EventProcessed, ErrorResponse> {
+        let _gate = self.gate.lock().unwrap();
+        self.sent.send_v2(event)
+    }
+
+    fn send_change(&self, event: &Value) -> Result<v2::ChangeEventProcessed, ErrorResponse> {
+        let _gate = self.gate.lock().unwrap();
+        self.sent.send_change(event)
+    }
+}
+
+fn panic_in_thread(message: &'static str) {
+    assert!(thread::spawn(move || panic!("{}", message)).join().is_err());
+}
+
+#[test]
+fn test_panic_hook() {
+    let sink = Arc::new(CaptureSink::new());
+    install_panic_hook(
+        PanicHookConfig::new("key".into())
+            .incident_key("panics".into())
+            .sink(sink.clone())
+    ).unwrap();
+
+    let result = thread::Builder::new()
+        .name("worker".into())
+        .spawn(|| panic!("disk {} full", "/var"))
+        .unwrap()
+        .join();
+    assert!(result.is_err());
+
+    let events = sink.events();
+    assert_eq!(events.len(), 1);
+
+    let event = &events[0].1;
+    assert_eq!(event["incident_key"], "panics");
+    assert_eq!(event["details"]["message"], "disk /var full");
+    assert_eq!(event["details"]["thread"], "worker");
+    assert!(event["description"].as_str().unwrap()
+        .starts_with("thread 'worker' panicked at 'disk /var full', tests/panic_hook.rs:"));
+    assert!(event["details"]["backtrace"].as_str().unwrap().len() > 0);
+
+    // Panics racing each other on different threads are all paged.
+    sink.clear();
+    let barrier = Arc::new(Barrier::new(4));
+    let workers: Vec<_> = (0..4).map(|i| {
+        let barrier = barrier.clone();
+        thread::spawn(move || {
+            barrier.wait();
+            panic!("worker {} failed", i);
+        })
+    }).collect();
+    for worker in workers {
+        assert!(worker.join().is_err());
+    }
+
+    let mut messages: Vec<String> = sink.events().iter()
+        .map(|&(_, ref event)| event["details"]["message"].as_str().unwrap().to_owned())
+        .collect();
+    messages.sort();
+    assert_eq!(
+        messages,
+        vec!["worker 0 failed", "worker 1 failed", "worker 2 failed", "worker 3 failed"]
+    );
+
+    // Without a sink of its own, a hook sends through the default sink set
+    // by the time of the panic.
+    install_panic_hook(PanicHookConfig::new("key".into()).incident_key("late".into())).unwrap();
+    let late = Arc::new(CaptureSink::new());
+    set_default_sink(late.clone());
+    panic_in_thread("set up late");
+    late.assert_sent("trigger", "late");
+
+    // A sink that doesn't answer holds the panic up only until the timeout.
+    let gated = Arc::new(GatedSink::default());
+    install_panic_hook(
+        PanicHookConfig::new("key".into())
+            .incident_key("stuck".into())
+            .timeout(Duration::from_millis(100))
+            .sink(gated.clone())
+    ).unwrap();
+    {
+        let _gate = gated.gate.lock().unwrap();
+        let start = Instant::now();
+        panic_in_thread("stuck sending");
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert!(gated.sent.is_empty());
+    }
+}